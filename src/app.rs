@@ -1,6 +1,29 @@
+use std::{
+    fs::OpenOptions,
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
 use serde_json::Value;
 
-use crate::json::JsonContainer;
+use crate::{
+    compare::Compare,
+    config::Config,
+    convert::ConvertPicker,
+    diff::DiffView,
+    edit::path_to_pointer,
+    help::Help,
+    json::{count_inexact_numbers, node_pointers, JsonContainer},
+    keymap::KeyMap,
+    palette::Palette,
+    patch::{Operation, PatchPreview},
+    query::Query,
+    schema::{ErrorPanel, Schema, SchemaConfig, SchemaError},
+    table::TableView,
+    theme::Theme,
+};
 
 #[derive(Default)]
 pub enum CurrentScreen {
@@ -8,11 +31,112 @@ pub enum CurrentScreen {
     Main,
     Editing,
     Exiting,
+    Prompt,
+    Table,
 }
 
 pub enum CurrentlyEditing {
     Key,
-    Value,
+    Value(ValueKind),
+}
+
+/// what the text typed for a value must be
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ValueKind {
+    String,
+    Number,
+    Boolean,
+    Json, // any JSON, for null, arrays and objects
+}
+
+impl ValueKind {
+    pub fn of(value: &Value) -> Self {
+        match value {
+            Value::String(_) => ValueKind::String,
+            Value::Number(_) => ValueKind::Number,
+            Value::Bool(_) => ValueKind::Boolean,
+            _ => ValueKind::Json,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ValueKind::String => "string",
+            ValueKind::Number => "number",
+            ValueKind::Boolean => "boolean",
+            ValueKind::Json => "JSON",
+        }
+    }
+
+    /// the value of the typed text, or why it is not valid
+    pub fn parse(self, text: &str) -> Result<Value, String> {
+        match self {
+            ValueKind::String => Ok(Value::String(text.to_string())),
+            ValueKind::Number => text
+                .trim()
+                .parse()
+                .map(Value::Number)
+                .map_err(|_| "not a valid JSON number".to_string()),
+            ValueKind::Boolean => match text.trim() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                _ => Err("not true or false".to_string()),
+            },
+            ValueKind::Json => serde_json::from_str(text).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+pub enum PromptKind {
+    ExportCsv,
+    ImportCsv { infer_types: bool },
+    SaveAs,
+    GoToPath,
+    RenameKey,
+    SortArray,
+    ExportSchema,
+    IgnoreKeys,
+    ExportPatch,
+    ApplyPatch,
+    MergeFile,
+    ExportMergePatch,
+    Query,
+}
+
+/// a one-line text input shown in a popup, used by commands that need an argument
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub input: String,
+    pub previous: CurrentScreen, // the screen to go back to
+}
+
+impl Prompt {
+    pub fn new(kind: PromptKind, input: String, previous: CurrentScreen) -> Self {
+        Self {
+            kind,
+            input,
+            previous,
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self.kind {
+            PromptKind::ExportCsv => "Export to CSV/TSV file",
+            PromptKind::ImportCsv { infer_types: true } => "Import CSV/TSV file",
+            PromptKind::ImportCsv { infer_types: false } => "Import CSV/TSV file (as strings)",
+            PromptKind::SaveAs => "Save as",
+            PromptKind::GoToPath => "Go to path (/a/0 or a.0)",
+            PromptKind::RenameKey => "Rename key",
+            PromptKind::SortArray => "Sort by field (empty to sort by value)",
+            PromptKind::ExportSchema => "Export an inferred JSON Schema to",
+            PromptKind::IgnoreKeys => "Keys to ignore, separated by commas",
+            PromptKind::ExportPatch => "Export the edits as a JSON Patch to",
+            PromptKind::ApplyPatch => "Apply the JSON Patch file",
+            PromptKind::MergeFile => "Merge the JSON file",
+            PromptKind::ExportMergePatch => "Export the merge patch to",
+            PromptKind::Query => "Query (.a.b, .[], select(), map())",
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Default)]
@@ -27,61 +151,407 @@ pub enum InputFile {
 pub struct App<'a> {
     pub input_file: InputFile,
     pub json_container: JsonContainer<'a>,
+    pub original: Value, // the document as loaded or last saved
     pub index_edition: Option<usize>,
     pub current_screen: CurrentScreen, // the current screen the user is looking at, and will later determine what is rendered.
     pub currently_editing: Option<CurrentlyEditing>, // the optional state containing which of the key or value pair the user is editing. It is an option, because when the user is not directly editing a key-value pair, this will be set to `None`.
+    pub prompt: Option<Prompt>,
+    pub table: Option<TableView>,
+    pub help: Option<Help>,
+    pub palette: Option<Palette>,
+    pub convert: Option<ConvertPicker>,
+    pub status: Option<String>, // a message for the user, cleared on the next key press
+    pub theme: Theme,
+    pub keymap: KeyMap,
+    pub register: Option<(Option<String>, Value)>, // the yanked node, with its key when it had one
+    pub yanked_from: Option<String>, // the pointer of the yanked node, to paste it as a copy
+    pub patch: Vec<Operation>,       // the edits of the session
+    pub patch_preview: Option<PatchPreview>,
+    pub external_edit: Option<String>, // the node to open in an external editor, before the next draw
+    pub schema: Option<Schema>,
+    pub schema_config: SchemaConfig,
+    pub schema_errors: Vec<SchemaError>,
+    pub validated_revision: Option<u64>,
+    pub error_panel: Option<ErrorPanel>,
+    pub diff: Option<DiffView>,
+    pub compare: Option<Compare>, // set when comparing two files instead of editing one
+    pub key_suggestion: Option<usize>, // the highlighted property name while editing a key
+    pub value_choice: Option<usize>, // the highlighted value of an enum
+    pub query: Option<Query>,
+    pub last_query: String, // the expression to start the next query from
+    pub readonly: bool,
+    pub indent: Option<String>, // of the written JSON, compact without
+    pub newline: bool,          // whether the written JSON ends with a newline
+    pub output: Option<String>, // where to write instead of the edited file
+    pub no_mouse: bool,
+    pub new_member: bool, // the edited node was just added, its value follows its key
     phantom: std::marker::PhantomData<&'a ()>,
 }
 
+/// the text of `value`, compact or indented with `indent`
+pub fn to_json_text(value: &Value, indent: Option<&str>) -> serde_json::Result<String> {
+    let Some(indent) = indent else {
+        return serde_json::to_string(value);
+    };
+    let mut text = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut ser = serde_json::Serializer::with_formatter(&mut text, formatter);
+    value.serialize(&mut ser)?;
+    Ok(String::from_utf8(text).unwrap_or_default())
+}
+
+/// the layout of a JSON text to write it back the same: the indentation of its first
+/// indented line, none when it is compact, and whether it ends with a newline
+pub fn detect_layout(text: &str) -> (Option<String>, bool) {
+    let indent = text.lines().skip(1).find_map(|line| {
+        let indent: String = line
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect();
+        (!indent.is_empty()).then_some(indent)
+    });
+    (indent, text.ends_with('\n'))
+}
+
+/// create a new file `dir/prefix.<unique>.extension`, never replacing nor following an
+/// existing file, it is only readable by its owner
+pub fn create_unique(
+    dir: &Path,
+    prefix: &str,
+    extension: &str,
+) -> std::io::Result<(PathBuf, std::fs::File)> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.subsec_nanos());
+    for attempt in 0..100 {
+        let name = format!(
+            "{}.{}-{}-{}.{}",
+            prefix,
+            std::process::id(),
+            nanos,
+            attempt,
+            extension
+        );
+        let path = dir.join(name);
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(ErrorKind::AlreadyExists.into())
+}
+
+/// write through a temporary file renamed over `path`, so that it is never left half
+/// written; a symbolic link is followed and the permissions of the file are kept
+pub fn write_atomic(path: &str, contents: &[u8]) -> std::io::Result<()> {
+    let target = std::fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+    let dir = match target.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = target
+        .file_name()
+        .map_or("json-editor".into(), |name| name.to_string_lossy());
+    // a new file gets the default permissions, not the private ones of the temporary file
+    let created = match OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&target)
+    {
+        Ok(_) => true,
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => false,
+        Err(e) => return Err(e),
+    };
+    let (temporary, mut file) = create_unique(dir, &format!(".{}", name), "tmp")?;
+    let result = file
+        .write_all(contents)
+        .and_then(|()| std::fs::metadata(&target))
+        .and_then(|metadata| std::fs::set_permissions(&temporary, metadata.permissions()))
+        .and_then(|()| std::fs::rename(&temporary, &target));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temporary);
+        if created {
+            let _ = std::fs::remove_file(&target);
+        }
+    }
+    result
+}
+
 impl App<'_> {
     pub fn new(input_file: Option<String>) -> Self {
         let (input_file, default_json) = match input_file.clone() {
             Some(input_file) => match std::fs::read_to_string(&input_file) {
                 Ok(json) => {
                     let default_json = serde_json::from_str(&json).expect("Could not parse json");
-                    (InputFile::Edition(input_file), default_json)
+                    let mut app = Self::with_document(InputFile::Edition(input_file), default_json);
+                    (app.indent, app.newline) = detect_layout(&json);
+                    return app;
                 }
                 Err(_) => (InputFile::Creation(input_file), Value::Null),
             },
             None => (InputFile::None, Value::Null),
         };
+        Self::with_document(input_file, default_json)
+    }
 
-        Self {
+    /// an app editing an existing file, failing instead of creating it when it cannot be read
+    pub fn open(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let document = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path, e))?;
+        let mut app = Self::with_document(InputFile::Edition(path.to_string()), document);
+        (app.indent, app.newline) = detect_layout(&text);
+        Ok(app)
+    }
+
+    fn with_document(input_file: InputFile, default_json: Value) -> Self {
+        // numbers are kept as written, but warn that other tools may not read them back the same
+        let inexact = count_inexact_numbers(&default_json);
+        let mut status = (inexact > 0).then(|| {
+            format!(
+                "Warning: {} number(s) exceed 64-bit float precision, they are kept as written",
+                inexact
+            )
+        });
+
+        let config = Config::load().unwrap_or_else(|e| {
+            status = Some(format!("Config error: {}", e));
+            Config::default()
+        });
+        let theme = config.resolve_theme(None).unwrap_or_else(|e| {
+            status = Some(format!("Config error: {}", e));
+            Theme::default()
+        });
+        let keymap = KeyMap::new(&config.keys).unwrap_or_else(|e| {
+            status = Some(format!("Config error: {}", e));
+            KeyMap::new(&Default::default()).unwrap_or_default()
+        });
+
+        let mut app = Self {
             input_file,
+            original: default_json.clone(),
             json_container: JsonContainer::new(default_json),
+            status,
+            theme,
+            keymap,
+            schema_config: config.schema,
             ..Default::default()
-        }
+        };
+        app.discover_schema();
+        app
     }
 
-    pub fn write_output(&self) {
+    pub fn write_output(&self) -> std::io::Result<()> {
+        if let Some(output) = &self.output {
+            return self.write_json(output);
+        }
         match &self.input_file {
-            InputFile::Creation(filepath) => {
-                std::fs::File::create(filepath).expect("Could not create file");
-                self.write_json(filepath).expect("Could not write json");
-            }
-            InputFile::Edition(filepath) => {
-                self.write_json(filepath).expect("Could not write json");
-            }
-            InputFile::None => {
-                self.print_json().expect("Could not print json");
+            InputFile::Creation(filepath) | InputFile::Edition(filepath) => {
+                self.write_json(filepath)
             }
+            InputFile::None => Ok(self.print_json()?),
         }
     }
 
+    /// whether the document cannot be changed, saying so in the status
+    pub fn read_only(&mut self) -> bool {
+        if self.readonly {
+            self.status = Some("Read-only, the document cannot be changed".to_string());
+        }
+        self.readonly
+    }
+
+    /// use a theme of the config file or a built-in one instead of the configured theme
+    pub fn use_theme(&mut self, name: &str) -> Result<(), String> {
+        self.theme = Config::load()?.resolve_theme(Some(name))?;
+        Ok(())
+    }
+
+    /// path of the file being edited, if any
+    pub fn input_path(&self) -> Option<&str> {
+        match &self.input_file {
+            InputFile::Creation(path) | InputFile::Edition(path) => Some(path),
+            InputFile::None => None,
+        }
+    }
+
+    /// write the file without quitting
+    pub fn save(&mut self) {
+        match self
+            .output
+            .clone()
+            .or(self.input_path().map(str::to_string))
+        {
+            Some(path) => self.save_as(&path),
+            None => self.status = Some("No file to save to, use save as".to_string()),
+        }
+    }
+
+    /// write to `path`, which becomes the edited file
+    pub fn save_as(&mut self, path: &str) {
+        if self.read_only() || self.save_blocked() {
+            return;
+        }
+        let path = path.to_string();
+        self.status = Some(match self.write_json(&path) {
+            Ok(()) => {
+                if self.output.as_ref() != Some(&path) {
+                    self.input_file = InputFile::Edition(path.clone());
+                }
+                self.original = self.json_container.inner.clone();
+                match self.schema_errors.len() {
+                    0 => format!("Saved {}", path),
+                    errors => format!("Saved {} with {} schema error(s)", path, errors),
+                }
+            }
+            Err(e) => format!("Could not save {}: {}", path, e),
+        });
+    }
+
     pub fn print_json(&self) -> serde_json::Result<()> {
-        let output = serde_json::to_string(&self.json_container.inner)?;
+        let output = to_json_text(&self.json_container.inner, self.indent.as_deref())?;
         println!("{}", output);
         Ok(())
     }
 
-    pub fn write_json(&self, path: &str) -> serde_json::Result<()> {
-        let output =
-            serde_json::to_string(&self.json_container.inner).expect("Could not serialize json");
-        std::fs::write(path, output).expect("Could not write to file");
-        Ok(())
+    pub fn write_json(&self, path: &str) -> std::io::Result<()> {
+        let mut output = to_json_text(&self.json_container.inner, self.indent.as_deref())?;
+        if self.newline {
+            output.push('\n');
+        }
+        write_atomic(path, output.as_bytes())
+    }
+
+    /// path of the input file without its extension, used to suggest names for exported files
+    pub fn input_stem(&self) -> String {
+        match &self.input_file {
+            InputFile::Creation(file) | InputFile::Edition(file) => std::path::Path::new(file)
+                .with_extension("")
+                .to_string_lossy()
+                .to_string(),
+            InputFile::None => "output".to_string(),
+        }
+    }
+
+    /// JSON Pointer of the selected node: the value under `input_cursor` inside the
+    /// element at `index_edition`, the element itself, or the root
+    pub fn selected_pointer(&self) -> String {
+        let Some(idx_json) = self.index_edition else {
+            return String::new();
+        };
+        let base = format!("/{}", idx_json);
+        match (
+            self.json_container.input_cursor,
+            self.json_container.inner.pointer(&base),
+        ) {
+            (Some(cursor), Some(value)) => {
+                let mut pointers = Vec::new();
+                node_pointers(value, &base, &mut pointers);
+                pointers
+                    .get(cursor.saturating_sub(1))
+                    .cloned()
+                    .unwrap_or(base)
+            }
+            _ => base,
+        }
+    }
+
+    /// move the selection to the node at `pointer`, as far as the two-level selection
+    /// (element of the root array, then line in the element) can reach it
+    pub fn select_pointer(&mut self, pointer: &str) {
+        let inner = &self.json_container.inner;
+        let first = pointer
+            .split('/')
+            .nth(1)
+            .and_then(|idx| idx.parse::<usize>().ok());
+        let idx_json = match (inner.as_array(), first) {
+            (Some(array), Some(idx)) if idx < array.len() => idx,
+            _ => {
+                self.index_edition = None;
+                self.reset_cursor();
+                return;
+            }
+        };
+        let base = format!("/{}", idx_json);
+        let mut pointers = Vec::new();
+        if let Some(value) = inner.pointer(&base) {
+            node_pointers(value, &base, &mut pointers);
+        }
+        self.index_edition = Some(idx_json);
+        self.json_container.max_cursor = Some(pointers.len());
+        // the deepest displayed node containing the pointer
+        self.json_container.input_cursor = pointers
+            .iter()
+            .rposition(|candidate| {
+                pointer == candidate || pointer.starts_with(&format!("{}/", candidate))
+            })
+            .map(|position| position + 1);
+    }
+
+    pub fn reset_cursor(&mut self) {
+        self.json_container.input_cursor = None;
+        self.json_container.max_cursor = None;
+    }
+
+    /// go to a JSON Pointer (`/a/0`) or a dotted path (`a.0`)
+    pub fn go_to_path(&mut self, path: &str) {
+        let pointer = path_to_pointer(path);
+        if self.json_container.inner.pointer(&pointer).is_some() {
+            self.select_pointer(&pointer);
+        } else {
+            self.status = Some(format!("No node at {}", path));
+        }
+    }
+
+    /// JSON Pointer of the array the tabular commands work on: the selected node when
+    /// it is an array, the root otherwise
+    pub fn selected_array_pointer(&self) -> String {
+        let pointer = self.selected_pointer();
+        match self.json_container.inner.pointer(&pointer) {
+            Some(value) if value.is_array() => pointer,
+            _ => String::new(),
+        }
+    }
+
+    /// replace the node at `pointer`, returns false if it does not exist
+    pub fn replace_at(&mut self, pointer: &str, value: Value) -> bool {
+        match self.json_container.inner.pointer_mut(pointer) {
+            Some(node) => {
+                if *node != value {
+                    *node = value.clone();
+                    self.record(Operation::Replace {
+                        path: pointer.to_string(),
+                        value,
+                    });
+                }
+                self.json_container.mark_dirty();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// pointer of the container whose brackets are highlighted: the selected node,
+    /// or its parent when the selected node is a scalar
+    pub fn focused_container_pointer(&self) -> String {
+        let pointer = self.selected_pointer();
+        match self.json_container.inner.pointer(&pointer) {
+            Some(value) if value.is_array() || value.is_object() => pointer,
+            _ => match pointer.rfind('/') {
+                Some(pos) => pointer[..pos].to_string(),
+                None => String::new(),
+            },
+        }
     }
 
     pub fn render_json(&mut self) {
-        self.json_container.create_lines(self.index_edition);
+        self.revalidate();
+        let focused = self.focused_container_pointer();
+        self.json_container
+            .create_lines(self.index_edition, &focused, &self.theme);
     }
 }
@@ -0,0 +1,109 @@
+use std::io::Write;
+
+use serde_json::Value;
+
+use crate::{app::App, edit::display_pointer, keymap::Context};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// the OSC 52 escape sequence asking the terminal to put `text` in the system clipboard
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+/// pasted text is JSON when it parses, a string otherwise
+pub fn parse_pasted(text: &str) -> Value {
+    serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+impl App<'_> {
+    /// copy `text` to the system clipboard through the terminal, which needs no platform library
+    /// but only works in terminals supporting OSC 52
+    pub fn copy_to_clipboard(&self, text: &str) {
+        // the interface is drawn on stderr
+        let mut stderr = std::io::stderr();
+        let _ = stderr.write_all(osc52(text).as_bytes());
+        let _ = stderr.flush();
+    }
+
+    pub fn copy_selected_path(&mut self) {
+        let pointer = self.selected_pointer();
+        self.copy_to_clipboard(display_pointer(&pointer));
+        self.status = Some(format!("Copied path {}", display_pointer(&pointer)));
+    }
+
+    /// text pasted in the terminal: typed in the open text input, or inserted as a node
+    pub fn paste_text(&mut self, text: &str) {
+        if let Some(palette) = &mut self.palette {
+            palette.input.push_str(text);
+        } else if let Some(help) = &mut self.help {
+            help.search.push_str(text);
+        } else if let Some(prompt) = &mut self.prompt {
+            prompt.input.push_str(text);
+        } else if let Some(input) = self
+            .table
+            .as_mut()
+            .and_then(|table| table.cell_input.as_mut())
+        {
+            input.push_str(text);
+        } else if self.currently_editing.is_some() {
+            self.focused_buffer().insert_str(text);
+        } else if self.compare.is_none()
+            && matches!(self.key_context(), Some(Context::Main | Context::Editing))
+            && !self.read_only()
+        {
+            self.insert_after_selected(None, parse_pasted(text.trim()), None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osc52() {
+        assert_eq!(base64(b"a"), "YQ==");
+        assert_eq!(base64(b"ab"), "YWI=");
+        assert_eq!(base64(b"abc"), "YWJj");
+        assert_eq!(osc52("{\"a\":1}"), "\x1b]52;c;eyJhIjoxfQ==\x07");
+        assert_eq!(parse_pasted("[1, 2]"), serde_json::json!([1, 2]));
+        assert_eq!(parse_pasted("hello"), serde_json::json!("hello"));
+    }
+
+    #[test]
+    fn test_paste_behind_modal() {
+        let mut app = App::default();
+        app.json_container.inner = serde_json::json!([1]);
+        app.open_diff();
+        app.paste_text("2");
+        assert_eq!(app.json_container.inner, serde_json::json!([1]));
+        app.diff = None;
+        app.current_screen = crate::app::CurrentScreen::Exiting;
+        app.paste_text("2");
+        assert_eq!(app.json_container.inner, serde_json::json!([1]));
+        app.current_screen = crate::app::CurrentScreen::Main;
+        app.paste_text("2");
+        assert_ne!(app.json_container.inner, serde_json::json!([1]));
+    }
+}
@@ -0,0 +1,396 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::{
+    app::App,
+    edit::{display_pointer, path_to_pointer},
+    patch::{apply_operation, Operation},
+    Options,
+};
+
+pub const USAGE: &str = "usage: json-editor [options] [file]
+       json-editor --diff <left> <right>
+       json-editor get <file> <path>
+       json-editor set <file> <path> <json value>
+       json-editor delete <file> <path>
+       json-editor fmt <file>
+       json-editor validate <file> [--schema <schema>]";
+
+pub const HELP: &str = "options:
+  -h, --help            print this help
+  -V, --version         print the version
+      --readonly        browse without changing or writing the document
+      --format <format> write compact or pretty JSON
+      --indent <spaces> indent the written JSON, implies --format pretty
+  -o, --output <path>   write the result to this file instead of the edited one
+      --schema <path>   validate against this JSON Schema
+      --diff <a> <b>    compare two files
+      --no-mouse        leave the mouse to the terminal
+      --theme <name>    use this theme instead of the configured one
+      --path <path>     open focused on this JSON Pointer or dotted path";
+
+/// what the arguments of the interface ask for
+pub enum Cli {
+    Help,
+    Version,
+    Edit {
+        input_file: Option<String>,
+        options: Options,
+    },
+}
+
+/// read the flags and the file of the interface
+pub fn parse_options(args: &[String]) -> Result<Cli, String> {
+    let mut input_file: Option<String> = None;
+    let mut options = Options::default();
+    let mut pretty = None;
+    let mut indent = None;
+    let mut positional = false; // after `--`
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        // `--flag=value` is the same as `--flag value`
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") && !positional => {
+                (flag, Some(value.to_string()))
+            }
+            _ => (arg.as_str(), None),
+        };
+        let mut value = |flag: &str| {
+            inline
+                .clone()
+                .or_else(|| args.next().cloned())
+                .ok_or_else(|| format!("{} needs a value", flag))
+        };
+        let no_value = ["--", "--help", "--version", "--readonly", "--no-mouse"];
+        if inline.is_some() && no_value.contains(&flag) {
+            return Err(format!("{} takes no value", flag));
+        }
+        match flag {
+            _ if positional || !flag.starts_with('-') || flag == "-" => match &input_file {
+                Some(file) => {
+                    return Err(format!(
+                        "only one file can be edited, found {} and {}",
+                        file, arg
+                    ))
+                }
+                None => input_file = Some(arg.clone()),
+            },
+            "--" => positional = true,
+            "-h" | "--help" => return Ok(Cli::Help),
+            "-V" | "--version" => return Ok(Cli::Version),
+            "--readonly" => options.readonly = true,
+            "--no-mouse" => options.no_mouse = true,
+            "--format" => {
+                pretty = Some(match value(flag)?.as_str() {
+                    "compact" => false,
+                    "pretty" => true,
+                    format => {
+                        return Err(format!(
+                            "unknown format {}, the formats are compact and pretty",
+                            format
+                        ))
+                    }
+                })
+            }
+            "--indent" => {
+                let spaces = value(flag)?;
+                indent =
+                    Some(spaces.parse::<usize>().map_err(|_| {
+                        format!("--indent needs a number of spaces, found {}", spaces)
+                    })?);
+            }
+            "-o" | "--output" => options.output = Some(value(flag)?),
+            "--schema" => options.schema = Some(value(flag)?),
+            "--theme" => options.theme = Some(value(flag)?),
+            "--path" => options.path = Some(value(flag)?),
+            "--diff" => {
+                let left = value(flag).map_err(|_| "--diff needs two files")?;
+                let right = args.next().ok_or("--diff needs two files")?;
+                options.diff = Some((left, right.clone()));
+            }
+            flag => return Err(format!("unknown flag {}", flag)),
+        }
+    }
+    if let (Some(_), Some(file)) = (&options.diff, &input_file) {
+        return Err(format!(
+            "--diff compares two files, it cannot edit {}",
+            file
+        ));
+    }
+    match (pretty, indent) {
+        (Some(false), Some(_)) => return Err("--indent needs the pretty format".to_string()),
+        (Some(false), None) => options.compact = true,
+        (Some(true), None) => options.indent = Some(4),
+        (_, indent) => options.indent = indent,
+    }
+    Ok(Cli::Edit {
+        input_file,
+        options,
+    })
+}
+
+/// an edit run without the interface, on a file and a JSON Pointer or dotted path
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Get {
+        file: String,
+        path: String,
+    },
+    Set {
+        file: String,
+        path: String,
+        value: Value,
+    },
+    Delete {
+        file: String,
+        path: String,
+    },
+    Fmt {
+        file: String,
+    },
+    Validate {
+        file: String,
+        schema: Option<String>,
+    },
+}
+
+impl Command {
+    /// the subcommand of the arguments, `None` when they open the interface
+    pub fn parse(args: &[String]) -> Option<Result<Self, String>> {
+        let (name, args) = args.split_first()?;
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let wrong = || Err(format!("wrong arguments for {}", name));
+        Some(match (name.as_str(), args.as_slice()) {
+            ("get", [file, path]) => Ok(Command::Get {
+                file: file.to_string(),
+                path: path.to_string(),
+            }),
+            ("set", [file, path, value]) => match serde_json::from_str(value) {
+                Ok(value) => Ok(Command::Set {
+                    file: file.to_string(),
+                    path: path.to_string(),
+                    value,
+                }),
+                Err(e) => Err(format!("the value {} is not JSON: {}", value, e)),
+            },
+            ("delete", [file, path]) => Ok(Command::Delete {
+                file: file.to_string(),
+                path: path.to_string(),
+            }),
+            ("fmt", [file]) => Ok(Command::Fmt {
+                file: file.to_string(),
+            }),
+            ("validate", [file]) => Ok(Command::Validate {
+                file: file.to_string(),
+                schema: None,
+            }),
+            ("validate", [file, "--schema", schema]) => Ok(Command::Validate {
+                file: file.to_string(),
+                schema: Some(schema.to_string()),
+            }),
+            ("get" | "set" | "delete" | "fmt" | "validate", _) => wrong(),
+            _ => return None,
+        })
+    }
+
+    /// run the command, returns what to print
+    pub fn run(self) -> Result<String, String> {
+        match self {
+            Command::Get { file, path } => {
+                let app = App::open(&file)?;
+                let pointer = path_to_pointer(&path);
+                let value = app
+                    .json_container
+                    .inner
+                    .pointer(&pointer)
+                    .ok_or_else(|| format!("no node at {}", display_pointer(&pointer)))?;
+                serde_json::to_string_pretty(value).map_err(|e| e.to_string())
+            }
+            Command::Set { file, path, value } => {
+                let mut app = App::open(&file)?;
+                let pointer = path_to_pointer(&path);
+                let operation = match app.json_container.inner.pointer(&pointer) {
+                    Some(_) => Operation::Replace {
+                        path: pointer,
+                        value,
+                    },
+                    None => Operation::Add {
+                        path: pointer,
+                        value,
+                    },
+                };
+                edit(&mut app, &file, operation)
+            }
+            Command::Delete { file, path } => {
+                let mut app = App::open(&file)?;
+                let pointer = path_to_pointer(&path);
+                edit(&mut app, &file, Operation::Remove { path: pointer })
+            }
+            Command::Fmt { file } => {
+                // indented as it was, or with 4 spaces when it was compact
+                let mut app = App::open(&file)?;
+                app.indent.get_or_insert_with(|| "    ".to_string());
+                app.newline = true;
+                app.write_json(&file)
+                    .map_err(|e| format!("{}: {}", file, e))?;
+                Ok(String::new())
+            }
+            Command::Validate { file, schema } => {
+                let mut app = App::open(&file)?;
+                let named = app.json_container.inner.get("$schema").is_some();
+                if let Some(schema) = &schema {
+                    app.use_schema(Path::new(schema));
+                }
+                let Some(source) = app.schema.as_ref().map(|schema| schema.source.clone()) else {
+                    if schema.is_some() || named {
+                        return Err(app.status.unwrap_or_else(|| "no schema".to_string()));
+                    }
+                    return Ok(format!("{} is valid JSON, without a schema", file));
+                };
+                app.revalidate();
+                if app.schema_errors.is_empty() {
+                    return Ok(format!("{} matches {}", file, source));
+                }
+                let mut lines: Vec<String> = app
+                    .schema_errors
+                    .iter()
+                    .map(|error| format!("{}: {}", display_pointer(&error.pointer), error.message))
+                    .collect();
+                lines.push(format!(
+                    "{} does not match {}: {} error(s)",
+                    file,
+                    source,
+                    app.schema_errors.len()
+                ));
+                Err(lines.join("\n"))
+            }
+        }
+    }
+}
+
+/// apply one operation and save, the schema can block it like in the interface
+fn edit(app: &mut App, file: &str, operation: Operation) -> Result<String, String> {
+    apply_operation(&mut app.json_container.inner, &operation)?;
+    app.json_container.mark_dirty();
+    app.record(operation);
+    if app.save_blocked() {
+        return Err(app.status.take().unwrap_or_default());
+    }
+    app.write_json(file)
+        .map_err(|e| format!("{}: {}", file, e))?;
+    Ok(String::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::app::create_unique;
+
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_commands() {
+        assert_eq!(Command::parse(&args(&["file.json"])), None);
+        assert_eq!(
+            Command::parse(&args(&["set", "a.json", "/a", "x"])),
+            Some(Err(
+                "the value x is not JSON: expected value at line 1 column 1".to_string()
+            ))
+        );
+        assert_eq!(
+            Command::parse(&args(&["delete", "a.json"])),
+            Some(Err("wrong arguments for delete".to_string()))
+        );
+
+        let parsed = parse_options(&args(&[
+            "--readonly",
+            "-o",
+            "out.json",
+            "--indent=2",
+            "a.json",
+        ]));
+        let Ok(Cli::Edit {
+            input_file: Some(input_file),
+            options,
+        }) = parsed
+        else {
+            panic!("not parsed");
+        };
+        assert_eq!(input_file, "a.json");
+        assert!(options.readonly);
+        assert_eq!(options.output.as_deref(), Some("out.json"));
+        assert_eq!(options.indent, Some(2));
+        assert!(matches!(
+            parse_options(&args(&["a.json", "-h"])),
+            Ok(Cli::Help)
+        ));
+        for (line, error) in [
+            (&["--bogus"][..], "unknown flag --bogus"),
+            (&["--output"], "--output needs a value"),
+            (
+                &["a.json", "b.json"],
+                "only one file can be edited, found a.json and b.json",
+            ),
+            (&["--readonly=false"], "--readonly takes no value"),
+            (&["--no-mouse=0"], "--no-mouse takes no value"),
+            (
+                &["--diff", "a.json", "b.json", "c.json"],
+                "--diff compares two files, it cannot edit c.json",
+            ),
+            (
+                &["--format", "compact", "--indent", "2"],
+                "--indent needs the pretty format",
+            ),
+        ] {
+            assert_eq!(parse_options(&args(line)).err().as_deref(), Some(error));
+        }
+
+        let (file, _) = create_unique(&std::env::temp_dir(), "json-editor-test", "json").unwrap();
+        let file = file.to_string_lossy().to_string();
+        std::fs::write(&file, r#"{"a": {"b": [1, 2]}, "c": "x"}"#).unwrap();
+        let run = |line: &[&str]| {
+            let mut line = args(line);
+            line.insert(1, file.clone());
+            Command::parse(&line).unwrap().unwrap().run()
+        };
+        assert_eq!(run(&["set", "a.b.0", "\"y\""]), Ok(String::new()));
+        assert_eq!(run(&["set", "/d", "{}"]), Ok(String::new()));
+        assert_eq!(run(&["delete", "/c"]), Ok(String::new()));
+        assert_eq!(run(&["get", "/a/b/0"]), Ok("\"y\"".to_string()));
+        assert_eq!(run(&["get", "/c"]), Err("no node at /c".to_string()));
+        assert_eq!(run(&["delete", "/e/f"]), Err("no node at /e/f".to_string()));
+        let document: Value =
+            serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+        assert_eq!(document, json!({"a": {"b": ["y", 2]}, "d": {}}));
+        assert_eq!(run(&["fmt"]), Ok(String::new()));
+        assert!(std::fs::read_to_string(&file)
+            .unwrap()
+            .starts_with("{\n    \"a\": {\n"));
+
+        // a pretty file stays pretty, with its indentation, newline and permissions
+        std::fs::write(&file, "{\n  \"a\": [\n    1\n  ]\n}\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o640)).unwrap();
+        }
+        assert_eq!(run(&["set", "/b", "true"]), Ok(String::new()));
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "{\n  \"a\": [\n    1\n  ],\n  \"b\": true\n}\n"
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&file).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o640);
+        }
+        std::fs::remove_file(&file).unwrap();
+    }
+}
@@ -0,0 +1,305 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::Rect,
+    text::Line,
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use serde_json::Value;
+
+use crate::{
+    app::{detect_layout, to_json_text, write_atomic, App, PromptKind},
+    diff::{diff_rows, revert, DiffOptions, DiffView},
+    edit::display_pointer,
+};
+
+/// two files shown side by side with their differences, to merge them
+pub struct Compare {
+    pub paths: [String; 2],
+    pub documents: [Value; 2],
+    pub modified: [bool; 2],
+    pub layouts: [(Option<String>, bool); 2], // the indentation and final newline of each file
+    pub options: DiffOptions,
+    pub view: DiffView,
+    pub quitting: bool, // quit was asked once with unsaved changes
+}
+
+impl Compare {
+    /// compute the differences again, keeping the selection around the same row
+    fn refresh(&mut self) {
+        let selected = self.view.selected;
+        self.view.rows = diff_rows(&self.documents[0], &self.documents[1], &self.options);
+        let next = self
+            .view
+            .changes()
+            .find(|idx| *idx >= selected)
+            .or_else(|| self.view.changes().last());
+        self.view.selected = next.unwrap_or_default();
+    }
+
+    /// copy the selected difference from the left side to the right one, or the other way
+    fn copy(&mut self, to_right: bool) -> Result<String, String> {
+        let row = &self.view.rows[self.view.selected];
+        let Some(change) = row.change else {
+            return Err("no difference selected".to_string());
+        };
+        let pointer = row.pointer.clone();
+        let [left, right] = &mut self.documents;
+        if to_right {
+            revert(right, left, &pointer, change)?;
+        } else {
+            revert(left, right, &pointer, change.mirror())?;
+        }
+        self.modified[to_right as usize] = true;
+        self.quitting = false;
+        self.refresh();
+        Ok(format!(
+            "Copied {} to {}",
+            display_pointer(&pointer),
+            self.paths[to_right as usize]
+        ))
+    }
+
+    fn save(&mut self) -> Result<String, String> {
+        let mut saved = Vec::new();
+        for side in 0..2 {
+            if !self.modified[side] {
+                continue;
+            }
+            let path = &self.paths[side];
+            let (indent, newline) = &self.layouts[side];
+            to_json_text(&self.documents[side], indent.as_deref())
+                .map_err(|e| e.to_string())
+                .and_then(|text| {
+                    let text = if *newline { text + "\n" } else { text };
+                    write_atomic(path, text.as_bytes()).map_err(|e| e.to_string())
+                })
+                .map_err(|e| format!("Could not save {}: {}", path, e))?;
+            self.modified[side] = false;
+            saved.push(path.as_str());
+        }
+        match saved.is_empty() {
+            true => {
+                Err("Nothing to save, copy differences from one side to the other first".into())
+            }
+            false => Ok(format!("Saved {}", saved.join(" and "))),
+        }
+    }
+}
+
+impl App<'_> {
+    /// an app comparing two files instead of editing one
+    pub fn compare_files(left: &str, right: &str) -> Result<Self, String> {
+        let read = |path: &str| -> Result<(Value, (Option<String>, bool)), String> {
+            let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            let value = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path, e))?;
+            Ok((value, detect_layout(&text)))
+        };
+        let (left_value, left_layout) = read(left)?;
+        let (right_value, right_layout) = read(right)?;
+        let documents = [left_value, right_value];
+        let options = DiffOptions::default();
+        let mut view = DiffView {
+            rows: diff_rows(&documents[0], &documents[1], &options),
+            selected: 0,
+            side_by_side: true,
+        };
+        let first = view.changes().next();
+        view.selected = first.unwrap_or_default();
+        let mut app = App::new(None);
+        app.compare = Some(Compare {
+            paths: [left.to_string(), right.to_string()],
+            documents,
+            modified: [false; 2],
+            layouts: [left_layout, right_layout],
+            options,
+            view,
+            quitting: false,
+        });
+        Ok(app)
+    }
+
+    /// keys of the comparison, returns whether to quit like an action
+    pub fn handle_compare_key(&mut self, key: KeyEvent) -> Option<bool> {
+        if matches!(key.code, KeyCode::Char('>' | '<' | 's'))
+            && self.compare.is_some()
+            && self.read_only()
+        {
+            return None;
+        }
+        let compare = self.compare.as_mut()?;
+        let result = match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => {
+                if compare.modified.contains(&true) && !compare.quitting {
+                    compare.quitting = true;
+                    Err("Unsaved changes, s to save them, q again to quit".to_string())
+                } else {
+                    return Some(false);
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('n') => {
+                compare.view.step(true);
+                return None;
+            }
+            KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('p') => {
+                compare.view.step(false);
+                return None;
+            }
+            KeyCode::Char('>') => compare.copy(true),
+            KeyCode::Char('<') => compare.copy(false),
+            KeyCode::Char('a') => {
+                compare.options.ignore_array_order = !compare.options.ignore_array_order;
+                compare.refresh();
+                Ok(match compare.options.ignore_array_order {
+                    true => "Array order ignored".to_string(),
+                    false => "Array order compared".to_string(),
+                })
+            }
+            KeyCode::Char('i') => {
+                let input = compare.options.ignored_keys.join(", ");
+                self.open_prompt(PromptKind::IgnoreKeys, input);
+                return None;
+            }
+            KeyCode::Char('s') => compare.save(),
+            _ => return None,
+        };
+        self.status = Some(result.unwrap_or_else(|e| e));
+        None
+    }
+
+    /// the keys left out of the comparison, separated by commas
+    pub fn set_ignored_keys(&mut self, input: &str) {
+        let Some(compare) = &mut self.compare else {
+            return;
+        };
+        compare.options.ignored_keys = input
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(str::to_string)
+            .collect();
+        compare.refresh();
+    }
+
+    pub fn render_compare_title(&self) -> Option<String> {
+        let compare = self.compare.as_ref()?;
+        Some(format!(
+            "Comparing {} and {} - {} difference(s)",
+            compare.paths[0],
+            compare.paths[1],
+            compare.view.changes().count()
+        ))
+    }
+
+    pub fn render_compare_keys(&self) -> Paragraph<'static> {
+        Paragraph::new(Line::styled(
+            "n/p move, >/< copy, a order, i keys, s save, q quit",
+            self.theme.hint,
+        ))
+        .block(Block::default().borders(Borders::ALL))
+    }
+
+    /// the two files in the panes of the main screen, scrolled together
+    pub fn render_compare(&self, frame: &mut Frame, panes: [Rect; 2]) {
+        let Some(compare) = &self.compare else {
+            return;
+        };
+        for (side, area) in panes.into_iter().enumerate() {
+            let modified = if compare.modified[side] { " *" } else { "" };
+            let mut block = Block::default()
+                .title(format!("{}{}", compare.paths[side], modified))
+                .borders(Borders::ALL)
+                .border_style(self.theme.border_inactive);
+            if side == 1 {
+                let mut ignored = Vec::new();
+                if compare.options.ignore_array_order {
+                    ignored.push("array order".to_string());
+                }
+                ignored.extend(
+                    compare
+                        .options
+                        .ignored_keys
+                        .iter()
+                        .map(|key| format!("\"{}\"", key)),
+                );
+                if !ignored.is_empty() {
+                    block = block.title_bottom(Line::styled(
+                        format!("ignoring {}", ignored.join(", ")),
+                        self.theme.hint,
+                    ));
+                }
+            }
+            let inner = block.inner(area);
+            let (lines, selected) = self.diff_lines(&compare.view, Some(side == 0));
+            // keep the selected difference in the middle
+            let scroll = selected.saturating_sub(inner.height as usize / 2) as u16;
+            frame.render_widget(Paragraph::new(lines).scroll((scroll, 0)).block(block), area);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn compare(left: Value, right: Value, ignore_array_order: bool) -> Compare {
+        let options = DiffOptions {
+            ignore_array_order,
+            ..Default::default()
+        };
+        let mut compare = Compare {
+            paths: ["left.json".to_string(), "right.json".to_string()],
+            view: DiffView {
+                rows: diff_rows(&left, &right, &options),
+                selected: 0,
+                side_by_side: true,
+            },
+            documents: [left, right],
+            modified: [false; 2],
+            layouts: [(None, false), (None, false)],
+            options,
+            quitting: false,
+        };
+        compare.refresh();
+        compare
+    }
+
+    #[test]
+    fn test_copy() {
+        let left = json!({"a": 1, "b": [1, 2], "c": true});
+        let right = json!({"a": 2, "b": [1, 2], "d": null});
+
+        let mut to_right = compare(left.clone(), right.clone(), false);
+        while to_right.view.changes().next().is_some() {
+            to_right.copy(true).unwrap();
+        }
+        assert_eq!(to_right.documents[1], left);
+        assert_eq!(to_right.modified, [false, true]);
+        assert!(to_right.copy(true).is_err());
+
+        let mut to_left = compare(left, right.clone(), false);
+        while to_left.view.changes().next().is_some() {
+            to_left.copy(false).unwrap();
+        }
+        assert_eq!(to_left.documents[0], right);
+        assert_eq!(to_left.modified, [true, false]);
+
+        // with the array order ignored, elements are copied by value
+        let left = json!({"tags": ["x", "y", "z"]});
+        let right = json!({"tags": ["w", "z", "x"]});
+        let mut unordered = compare(left.clone(), right.clone(), true);
+        assert_eq!(unordered.view.changes().count(), 2);
+        unordered.copy(true).unwrap();
+        unordered.copy(true).unwrap();
+        assert_eq!(unordered.documents[1], json!({"tags": ["z", "y", "x"]}));
+        assert_eq!(unordered.view.changes().count(), 0);
+
+        let mut unordered = compare(left, right, true);
+        unordered.copy(false).unwrap();
+        unordered.copy(false).unwrap();
+        assert_eq!(unordered.documents[0], json!({"tags": ["w", "x", "z"]}));
+        assert_eq!(unordered.view.changes().count(), 0);
+    }
+}
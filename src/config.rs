@@ -0,0 +1,94 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use serde::Deserialize;
+
+use crate::{
+    keymap::KeysConfig,
+    schema::SchemaConfig,
+    theme::{Theme, ThemeSpec},
+};
+
+/// the user config, read from `config.toml` in the config directory
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub theme: Option<String>,
+    pub themes: HashMap<String, ThemeSpec>,
+    pub keys: KeysConfig,
+    pub schema: SchemaConfig,
+}
+
+/// `$XDG_CONFIG_HOME/json-editor`, falling back to `~/.config/json-editor`,
+/// or `%APPDATA%\json-editor` on Windows
+pub fn config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(base.join("json-editor"))
+}
+
+impl Config {
+    /// read the config file, a missing file gives the default config
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = config_dir().map(|dir| dir.join("config.toml")) else {
+            return Ok(Self::default());
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e.message()))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    /// the theme named `name`, or the one of the config file, `NO_COLOR` taking precedence
+    pub fn resolve_theme(&self, name: Option<&str>) -> Result<Theme, String> {
+        if Theme::no_color_requested() {
+            return Ok(Theme::no_color());
+        }
+        let Some(name) = name.or(self.theme.as_deref()) else {
+            return Ok(Theme::default());
+        };
+        match self.themes.get(name) {
+            Some(spec) => spec
+                .to_theme()
+                .map_err(|e| format!("theme \"{}\": {}", name, e)),
+            None => Theme::builtin(name).ok_or_else(|| {
+                format!(
+                    "unknown theme \"{}\", the built-in themes are {}",
+                    name,
+                    Theme::BUILTIN.join(", ")
+                )
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::{Color, Modifier};
+
+    use super::*;
+
+    #[test]
+    fn test_custom_theme() {
+        let config: Config = toml::from_str(
+            r##"
+            theme = "mine"
+
+            [themes.mine]
+            base = "light"
+            key = { fg = "#0000ff", bold = true }
+            "##,
+        )
+        .unwrap();
+        let theme = config.resolve_theme(None).unwrap();
+        assert_eq!(theme.key.fg, Some(Color::Rgb(0, 0, 0xff)));
+        assert!(theme.key.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(theme.string, Theme::light().string);
+        assert!(config.resolve_theme(Some("missing")).is_err());
+    }
+}
@@ -0,0 +1,314 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::{Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+use serde_json::{Map, Number, Value};
+
+use crate::{app::App, edit::display_pointer, ui::centered_rect};
+
+/// the types a node can be converted to
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Conversion {
+    String,
+    Number,
+    Boolean,
+    Null,
+    Object,
+    Array,
+    Pairs, // an array of `[key, value]` pairs
+}
+
+impl Conversion {
+    pub const ALL: [Conversion; 7] = [
+        Conversion::String,
+        Conversion::Number,
+        Conversion::Boolean,
+        Conversion::Null,
+        Conversion::Object,
+        Conversion::Array,
+        Conversion::Pairs,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Conversion::String => "string",
+            Conversion::Number => "number",
+            Conversion::Boolean => "boolean",
+            Conversion::Null => "null",
+            Conversion::Object => "object",
+            Conversion::Array => "array",
+            Conversion::Pairs => "array of [key, value] pairs",
+        }
+    }
+}
+
+/// the converted value and a description of what the conversion loses
+pub struct Converted {
+    pub value: Value,
+    pub losses: Vec<String>,
+}
+
+/// short text of a value for messages
+fn summary(value: &Value) -> String {
+    let text = value.to_string();
+    match text.char_indices().nth(30) {
+        Some((pos, _)) => format!("{}...", &text[..pos]),
+        None => text,
+    }
+}
+
+/// convert `value` to another type: strings holding a value are parsed (`"42"` to `42`),
+/// a scalar becomes a one element array, an object becomes the array of its values or
+/// of its `[key, value]` pairs and back
+pub fn convert(value: &Value, to: Conversion) -> Result<Converted, String> {
+    let mut losses = Vec::new();
+    let value = match (to, value) {
+        (Conversion::String, Value::String(_)) => value.clone(),
+        (Conversion::String, Value::Number(number)) => Value::String(number.to_string()),
+        (Conversion::String, Value::Bool(boolean)) => Value::String(boolean.to_string()),
+        (Conversion::String, Value::Null) => Value::String(String::new()),
+        (Conversion::String, _) => {
+            losses.push("the structure becomes its JSON text".to_string());
+            Value::String(value.to_string())
+        }
+        (Conversion::Number, Value::Number(_)) => value.clone(),
+        (Conversion::Number, Value::String(text)) => text
+            .trim()
+            .parse::<Number>()
+            .map(Value::Number)
+            .map_err(|_| format!("{} is not a number", summary(value)))?,
+        (Conversion::Number, Value::Bool(boolean)) => Value::from(*boolean as u8),
+        (Conversion::Number, Value::Null) => Value::from(0),
+        (Conversion::Boolean, Value::Bool(_)) => value.clone(),
+        (Conversion::Boolean, Value::String(text)) => match text.trim() {
+            "true" | "1" => Value::Bool(true),
+            "false" | "0" | "" => Value::Bool(false),
+            _ => return Err(format!("{} is not a boolean", summary(value))),
+        },
+        (Conversion::Boolean, Value::Number(number)) => {
+            let truthy = number.to_string().parse::<f64>().unwrap_or(1.0) != 0.0;
+            if !matches!(number.to_string().as_str(), "0" | "1") {
+                losses.push(format!("the number {} becomes {}", number, truthy));
+            }
+            Value::Bool(truthy)
+        }
+        (Conversion::Boolean, Value::Null) => Value::Bool(false),
+        (Conversion::Null, Value::Null) => Value::Null,
+        (Conversion::Null, _) => {
+            losses.push(format!("the value {} is dropped", summary(value)));
+            Value::Null
+        }
+        (Conversion::Array, Value::Array(_)) | (Conversion::Pairs, Value::Array(_)) => {
+            value.clone()
+        }
+        (Conversion::Array, Value::Object(obj)) => {
+            if !obj.is_empty() {
+                let keys: Vec<&str> = obj.keys().map(String::as_str).collect();
+                losses.push(format!("the keys {} are dropped", keys.join(", ")));
+            }
+            Value::Array(obj.values().cloned().collect())
+        }
+        (Conversion::Pairs, Value::Object(obj)) => Value::Array(
+            obj.iter()
+                .map(|(key, value)| Value::Array(vec![Value::String(key.clone()), value.clone()]))
+                .collect(),
+        ),
+        (Conversion::Array, Value::Null) | (Conversion::Pairs, Value::Null) => {
+            Value::Array(Vec::new())
+        }
+        (Conversion::Array, _) | (Conversion::Pairs, _) => Value::Array(vec![value.clone()]),
+        (Conversion::Object, Value::Object(_)) => value.clone(),
+        (Conversion::Object, Value::Null) => Value::Object(Map::new()),
+        (Conversion::Object, Value::Array(array)) => {
+            let pairs: Option<Vec<(&String, &Value)>> = array
+                .iter()
+                .map(|item| match item.as_array().map(Vec::as_slice) {
+                    Some([Value::String(key), value]) => Some((key, value)),
+                    _ => None,
+                })
+                .collect();
+            let mut obj = Map::new();
+            match pairs.filter(|pairs| !pairs.is_empty()) {
+                Some(pairs) => {
+                    for (key, value) in pairs {
+                        if obj.insert(key.clone(), value.clone()).is_some() {
+                            losses.push(format!("the duplicated key {} keeps its last value", key));
+                        }
+                    }
+                }
+                // not pairs, the indexes become the keys
+                None => {
+                    for (idx, value) in array.iter().enumerate() {
+                        obj.insert(idx.to_string(), value.clone());
+                    }
+                }
+            }
+            Value::Object(obj)
+        }
+        (Conversion::Number, _) | (Conversion::Boolean, _) | (Conversion::Object, _) => {
+            return Err(format!(
+                "cannot convert {} to {}",
+                summary(value),
+                to.name()
+            ))
+        }
+    };
+    Ok(Converted { value, losses })
+}
+
+/// the popup choosing the type of the selected node, with a preview of the result
+pub struct ConvertPicker {
+    pub pointer: String,
+    pub state: ListState,
+}
+
+impl App<'_> {
+    pub fn open_convert(&mut self) {
+        let pointer = self.selected_pointer();
+        let Some(value) = self.json_container.inner.pointer(&pointer) else {
+            return;
+        };
+        // start on the next type, the current one converts to itself
+        let current = Conversion::ALL
+            .iter()
+            .position(|to| match to {
+                Conversion::String => value.is_string(),
+                Conversion::Number => value.is_number(),
+                Conversion::Boolean => value.is_boolean(),
+                Conversion::Null => value.is_null(),
+                Conversion::Object => value.is_object(),
+                Conversion::Array => value.is_array(),
+                Conversion::Pairs => false,
+            })
+            .unwrap_or_default();
+        let mut state = ListState::default();
+        state.select(Some((current + 1) % Conversion::ALL.len()));
+        self.convert = Some(ConvertPicker { pointer, state });
+    }
+
+    pub fn handle_convert_key(&mut self, key: KeyEvent) {
+        let Some(picker) = &mut self.convert else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => self.convert = None,
+            KeyCode::Up => picker.state.select_previous(),
+            KeyCode::Down => picker.state.select_next(),
+            KeyCode::Enter => {
+                let pointer = picker.pointer.clone();
+                let to = Conversion::ALL[picker
+                    .state
+                    .selected()
+                    .unwrap_or_default()
+                    .min(Conversion::ALL.len() - 1)];
+                self.convert = None;
+                self.convert_at(&pointer, to);
+            }
+            _ => {}
+        }
+    }
+
+    /// convert the node at `pointer`, keeping it selected
+    pub fn convert_at(&mut self, pointer: &str, to: Conversion) {
+        let Some(value) = self.json_container.inner.pointer(pointer) else {
+            return;
+        };
+        match convert(value, to) {
+            Ok(converted) => {
+                self.replace_at(pointer, converted.value);
+                self.select_pointer(pointer);
+                self.status = Some(format!(
+                    "Converted {} to {}",
+                    display_pointer(pointer),
+                    to.name()
+                ));
+            }
+            Err(e) => self.status = Some(format!("Cannot convert: {}", e)),
+        }
+    }
+
+    pub fn render_convert(&mut self, frame: &mut Frame) {
+        let Some(picker) = &mut self.convert else {
+            return;
+        };
+        let area = centered_rect(60, 50, frame.area());
+        frame.render_widget(Clear, area);
+        let block = Block::default()
+            .title(format!("Convert {}", display_pointer(&picker.pointer)))
+            .borders(Borders::ALL)
+            .style(self.theme.popup);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(30), Constraint::Min(1)])
+            .split(inner);
+
+        let items: Vec<ListItem> = Conversion::ALL
+            .iter()
+            .map(|to| ListItem::new(to.name()))
+            .collect();
+        let list = List::new(items).highlight_style(self.theme.cursor);
+        frame.render_stateful_widget(list, chunks[0], &mut picker.state);
+
+        let to = Conversion::ALL[picker
+            .state
+            .selected()
+            .unwrap_or_default()
+            .min(Conversion::ALL.len() - 1)];
+        let mut lines = Vec::new();
+        match self
+            .json_container
+            .inner
+            .pointer(&picker.pointer)
+            .map(|value| convert(value, to))
+        {
+            Some(Ok(converted)) => {
+                lines.push(Line::from(vec![
+                    Span::styled("Result: ", self.theme.hint),
+                    Span::raw(converted.value.to_string()),
+                ]));
+                for loss in converted.losses {
+                    lines.push(Line::styled(format!("Loses: {}", loss), self.theme.error));
+                }
+            }
+            Some(Err(e)) => lines.push(Line::styled(e, self.theme.error)),
+            None => {}
+        }
+        frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), chunks[1]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        let number = convert(&json!("42"), Conversion::Number).unwrap();
+        assert_eq!(number.value, json!(42));
+        assert!(number.losses.is_empty());
+        assert!(convert(&json!("abc"), Conversion::Number).is_err());
+        assert_eq!(
+            convert(&json!(1), Conversion::Array).unwrap().value,
+            json!([1])
+        );
+
+        let obj = json!({"a": 1, "b": 2});
+        let values = convert(&obj, Conversion::Array).unwrap();
+        assert_eq!(values.value, json!([1, 2]));
+        assert_eq!(values.losses.len(), 1);
+        let pairs = convert(&obj, Conversion::Pairs).unwrap();
+        assert_eq!(pairs.value, json!([["a", 1], ["b", 2]]));
+        assert_eq!(
+            convert(&pairs.value, Conversion::Object).unwrap().value,
+            obj
+        );
+    }
+}
@@ -0,0 +1,310 @@
+use serde_json::{Map, Number, Value};
+
+use crate::app::App;
+
+/// pick the delimiter from the file extension: tab for `.tsv`, comma otherwise
+pub fn delimiter_for(path: &str) -> char {
+    if path.to_lowercase().ends_with(".tsv") {
+        '\t'
+    } else {
+        ','
+    }
+}
+
+/// flatten a value into `(column, value)` pairs, nested keys are joined with dots
+/// and array elements are numbered from 1, the same naming `render_json_struct` uses
+fn flatten(value: &Value, prefix: &str, cells: &mut Vec<(String, Value)>) {
+    let join = |key: &str| {
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", prefix, key)
+        }
+    };
+    match value {
+        // an empty element has no cells, a nested one is kept as `{}`
+        Value::Object(obj) if obj.is_empty() && prefix.is_empty() => {}
+        Value::Object(obj) if !obj.is_empty() => {
+            for (key, value) in obj {
+                flatten(value, &join(key), cells);
+            }
+        }
+        Value::Array(array) if !array.is_empty() => {
+            for (i, value) in array.iter().enumerate() {
+                flatten(value, &join(&(i + 1).to_string()), cells);
+            }
+        }
+        _ => cells.push((prefix.to_string(), value.clone())),
+    }
+}
+
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(string) => string.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn quote(field: &str, delimiter: char) -> String {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// export an array of objects, the columns are the union of all flattened keys
+pub fn to_delimited(value: &Value, delimiter: char) -> Result<String, String> {
+    let array = value.as_array().ok_or("the value is not an array")?;
+    let mut columns: Vec<String> = Vec::new();
+    let mut rows = Vec::with_capacity(array.len());
+    for (i, element) in array.iter().enumerate() {
+        if !element.is_object() {
+            return Err(format!("element {} is not an object", i));
+        }
+        let mut cells = Vec::new();
+        flatten(element, "", &mut cells);
+        for (column, _) in &cells {
+            if !columns.contains(column) {
+                columns.push(column.clone());
+            }
+        }
+        rows.push(cells);
+    }
+
+    let separator = delimiter.to_string();
+    let mut output = columns
+        .iter()
+        .map(|column| quote(column, delimiter))
+        .collect::<Vec<_>>()
+        .join(&separator);
+    output.push('\n');
+    for cells in rows {
+        let line = columns
+            .iter()
+            .map(|column| {
+                cells
+                    .iter()
+                    .find(|(name, _)| name == column)
+                    .map(|(_, value)| quote(&cell_text(value), delimiter))
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>()
+            .join(&separator);
+        output.push_str(&line);
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// split the text into records, handling quoted fields with embedded delimiters and newlines
+fn parse_records(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(c);
+            }
+        } else if c == '"' {
+            in_quotes = true;
+        } else if c == delimiter {
+            record.push(std::mem::take(&mut field));
+        } else if c == '\n' || c == '\r' {
+            if c == '\r' && chars.peek() == Some(&'\n') {
+                chars.next();
+            }
+            record.push(std::mem::take(&mut field));
+            records.push(std::mem::take(&mut record));
+        } else {
+            field.push(c);
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+fn infer_type(field: &str) -> Value {
+    match field {
+        "" | "null" => Value::Null,
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => match serde_json::from_str::<Number>(field) {
+            Ok(number) => Value::Number(number),
+            Err(_) => Value::String(field.to_string()),
+        },
+    }
+}
+
+/// insert `value` at the dotted `column`, keeping the column as a plain key when
+/// a scalar is already in the way
+fn insert_dotted(obj: &mut Map<String, Value>, column: &str, value: Value) {
+    match column.split_once('.') {
+        Some((head, rest)) if !head.is_empty() && !rest.is_empty() => {
+            let child = obj
+                .entry(head.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+            match child.as_object_mut() {
+                Some(child) => insert_dotted(child, rest, value),
+                None => {
+                    obj.insert(column.to_string(), value);
+                }
+            }
+        }
+        _ => {
+            obj.insert(column.to_string(), value);
+        }
+    }
+}
+
+/// turn objects keyed "1".."n" back into arrays, the inverse of the array naming in `flatten`
+fn restore_arrays(value: Value) -> Value {
+    match value {
+        Value::Object(obj) => {
+            let is_array = !obj.is_empty()
+                && obj
+                    .keys()
+                    .enumerate()
+                    .all(|(i, key)| *key == (i + 1).to_string());
+            let obj: Map<String, Value> = obj
+                .into_iter()
+                .map(|(key, value)| (key, restore_arrays(value)))
+                .collect();
+            if is_array {
+                Value::Array(obj.into_iter().map(|(_, value)| value).collect())
+            } else {
+                Value::Object(obj)
+            }
+        }
+        other => other,
+    }
+}
+
+/// import delimited text as an array of objects, the first record being the header
+pub fn from_delimited(text: &str, delimiter: char, infer_types: bool) -> Result<Value, String> {
+    let mut records = parse_records(text, delimiter).into_iter();
+    let header = records.next().ok_or("the file is empty")?;
+    let mut array = Vec::new();
+    for (i, record) in records.enumerate() {
+        // a blank line, unless the only column of the element is empty
+        if record.len() == 1 && record[0].is_empty() && header.len() > 1 {
+            continue;
+        }
+        if record.len() > header.len() {
+            return Err(format!("record {} has more fields than the header", i + 1));
+        }
+        let mut obj = Map::new();
+        // the row of an empty element has only empty cells
+        if record.iter().all(String::is_empty) {
+            array.push(Value::Object(obj));
+            continue;
+        }
+        for (column, field) in header.iter().zip(record) {
+            let value = if infer_types {
+                infer_type(&field)
+            } else {
+                Value::String(field)
+            };
+            insert_dotted(&mut obj, column, value);
+        }
+        array.push(restore_arrays(Value::Object(obj)));
+    }
+    Ok(Value::Array(array))
+}
+
+impl App<'_> {
+    pub fn export_csv(&mut self, path: &str) {
+        let pointer = self.selected_array_pointer();
+        let value = self
+            .json_container
+            .inner
+            .pointer(&pointer)
+            .unwrap_or(&Value::Null);
+        let result = to_delimited(value, delimiter_for(path))
+            .and_then(|text| std::fs::write(path, text).map_err(|e| e.to_string()));
+        self.status = Some(match result {
+            Ok(()) => format!("Exported to {}", path),
+            Err(e) => format!("Export failed: {}", e),
+        });
+    }
+
+    pub fn import_csv(&mut self, path: &str, infer_types: bool) {
+        let result = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| from_delimited(&text, delimiter_for(path), infer_types));
+        self.status = Some(match result {
+            Ok(value) => {
+                let pointer = self.selected_pointer();
+                self.replace_at(&pointer, value);
+                format!("Imported {}", path)
+            }
+            Err(e) => format!("Import failed: {}", e),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let value = json!([
+            {"id": 1, "name": "a, b", "tags": ["x", "y"], "meta": {"ok": true}},
+            {"id": 2, "extra": null}
+        ]);
+        let text = to_delimited(&value, ',').unwrap();
+        assert_eq!(
+            text,
+            "id,name,tags.1,tags.2,meta.ok,extra\n1,\"a, b\",x,y,true,\n2,,,,,\n"
+        );
+        let imported = from_delimited(&text, ',', true).unwrap();
+        assert_eq!(imported[0]["name"], value[0]["name"]);
+        assert_eq!(imported[0]["tags"], value[0]["tags"]);
+        assert_eq!(imported[0]["meta"], value[0]["meta"]);
+        assert_eq!(imported[0]["extra"], Value::Null);
+        assert_eq!(imported[1]["id"], value[1]["id"]);
+    }
+
+    #[test]
+    fn test_empty_element() {
+        for value in [
+            json!([{"a": 1}, {}]),
+            json!([{"a": 1, "b": "x"}, {}, {"a": 2, "b": "y"}]),
+        ] {
+            let text = to_delimited(&value, ',').unwrap();
+            assert!(!text.starts_with(','));
+            assert_eq!(from_delimited(&text, ',', true).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_numbers_kept_exact() {
+        let text = "id,amount\n12345678901234567890,0.10000000000000000001\n";
+        let imported = from_delimited(text, ',', true).unwrap();
+        assert_eq!(imported[0]["id"].to_string(), "12345678901234567890");
+        assert_eq!(to_delimited(&imported, ',').unwrap(), text);
+    }
+
+    #[test]
+    fn test_import_without_inference() {
+        let imported = from_delimited("a\tb\n42\t\"x\"\"y\"\n", '\t', false).unwrap();
+        assert_eq!(imported, json!([{"a": "42", "b": "x\"y"}]));
+    }
+}
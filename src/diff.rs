@@ -0,0 +1,535 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use serde_json::{Map, Value};
+
+use crate::{
+    app::App,
+    edit::{display_pointer, remove_node, split_pointer},
+    json::escape_pointer_token,
+    patch::Operation,
+    theme::Theme,
+    ui::centered_rect,
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl ChangeKind {
+    /// the same change seen from the other side
+    pub fn mirror(self) -> Self {
+        match self {
+            ChangeKind::Added => ChangeKind::Removed,
+            ChangeKind::Removed => ChangeKind::Added,
+            ChangeKind::Changed => ChangeKind::Changed,
+        }
+    }
+
+    fn style(self, theme: &Theme) -> Style {
+        match self {
+            ChangeKind::Added => theme.added,
+            ChangeKind::Removed => theme.removed,
+            ChangeKind::Changed => theme.changed,
+        }
+    }
+}
+
+/// a line of the tree of two documents: the same node on both sides, or a change
+#[derive(Debug)]
+pub struct DiffRow {
+    pub depth: usize,
+    pub label: String, // `"key": ` for object members
+    pub old: Option<String>,
+    pub new: Option<String>,
+    pub change: Option<ChangeKind>,
+    pub pointer: String,
+}
+
+/// what is not a difference
+#[derive(Default, Clone)]
+pub struct DiffOptions {
+    pub ignore_array_order: bool,
+    pub ignored_keys: Vec<String>, // members with these keys are left out, at any depth
+}
+
+impl DiffOptions {
+    fn ignores(&self, key: &str) -> bool {
+        self.ignored_keys.iter().any(|ignored| ignored == key)
+    }
+
+    /// whether `old` and `new` are the same, up to the ignored differences
+    pub fn same(&self, old: &Value, new: &Value) -> bool {
+        match (old, new) {
+            (Value::Object(old), Value::Object(new)) => {
+                let kept = |obj: &'_ Map<String, Value>| {
+                    obj.keys().filter(|key| !self.ignores(key)).count()
+                };
+                kept(old) == kept(new)
+                    && old
+                        .iter()
+                        .filter(|(key, _)| !self.ignores(key))
+                        .all(|(key, value)| {
+                            new.get(key).is_some_and(|other| self.same(value, other))
+                        })
+            }
+            (Value::Array(old), Value::Array(new)) if self.ignore_array_order => {
+                old.len() == new.len() && self.unmatched(old, new).is_empty()
+            }
+            (Value::Array(old), Value::Array(new)) => {
+                old.len() == new.len() && old.iter().zip(new).all(|(old, new)| self.same(old, new))
+            }
+            (old, new) => old == new,
+        }
+    }
+
+    /// the indexes of the elements of `new` with no same element in `old`, each element
+    /// of `old` matching one element of `new` at most
+    fn unmatched(&self, old: &[Value], new: &[Value]) -> Vec<usize> {
+        let mut used = vec![false; old.len()];
+        (0..new.len())
+            .filter(|idx| {
+                let found = (0..old.len())
+                    .find(|candidate| !used[*candidate] && self.same(&old[*candidate], &new[*idx]));
+                if let Some(found) = found {
+                    used[found] = true;
+                }
+                found.is_none()
+            })
+            .collect()
+    }
+}
+
+/// the text of a node the same on both sides, its content is folded
+fn folded(value: &Value) -> String {
+    match value {
+        Value::Object(obj) if !obj.is_empty() => "{…}".to_string(),
+        Value::Array(array) if !array.is_empty() => "[…]".to_string(),
+        value => value.to_string(),
+    }
+}
+
+/// the tree of the differences between `old` and `new`: object members are matched by
+/// key, array elements by index (or by value when their order is ignored), the nodes
+/// without changes are folded
+pub fn diff_rows(old: &Value, new: &Value, options: &DiffOptions) -> Vec<DiffRow> {
+    let mut rows = Vec::new();
+    walk(
+        Some(old),
+        Some(new),
+        String::new(),
+        "",
+        0,
+        options,
+        &mut rows,
+    );
+    rows
+}
+
+fn walk(
+    old: Option<&Value>,
+    new: Option<&Value>,
+    label: String,
+    pointer: &str,
+    depth: usize,
+    options: &DiffOptions,
+    rows: &mut Vec<DiffRow>,
+) {
+    let row = |label: String, old: Option<String>, new: Option<String>, change| DiffRow {
+        depth,
+        label,
+        old,
+        new,
+        change,
+        pointer: pointer.to_string(),
+    };
+    let member = |key: &str| {
+        (
+            format!("{}: ", Value::from(key)),
+            format!("{}/{}", pointer, escape_pointer_token(key)),
+        )
+    };
+    match (old, new) {
+        (Some(old), Some(new)) if options.same(old, new) => {
+            rows.push(row(label, Some(folded(old)), Some(folded(new)), None))
+        }
+        (Some(Value::Object(old)), Some(Value::Object(new))) => {
+            rows.push(row(label, Some("{".into()), Some("{".into()), None));
+            for (key, value) in new.iter().filter(|(key, _)| !options.ignores(key)) {
+                let (label, pointer) = member(key);
+                let old = old.get(key);
+                walk(old, Some(value), label, &pointer, depth + 1, options, rows);
+            }
+            for (key, value) in old
+                .iter()
+                .filter(|(key, _)| !new.contains_key(*key) && !options.ignores(key))
+            {
+                let (label, pointer) = member(key);
+                walk(Some(value), None, label, &pointer, depth + 1, options, rows);
+            }
+            rows.push(row(String::new(), Some("}".into()), Some("}".into()), None));
+        }
+        (Some(Value::Array(old)), Some(Value::Array(new))) => {
+            rows.push(row(label, Some("[".into()), Some("[".into()), None));
+            if options.ignore_array_order {
+                // the elements found on one side only, with their index on that side
+                let added = options.unmatched(old, new);
+                let removed = options.unmatched(new, old);
+                for idx in 0..new.len() {
+                    let pointer = format!("{}/{}", pointer, idx);
+                    let (old, new) = match added.contains(&idx) {
+                        true => (None, Some(&new[idx])),
+                        false => (Some(&new[idx]), Some(&new[idx])),
+                    };
+                    walk(old, new, String::new(), &pointer, depth + 1, options, rows);
+                }
+                for idx in removed {
+                    let pointer = format!("{}/{}", pointer, idx);
+                    let old = Some(&old[idx]);
+                    walk(old, None, String::new(), &pointer, depth + 1, options, rows);
+                }
+                rows.push(row(String::new(), Some("]".into()), Some("]".into()), None));
+                return;
+            }
+            for idx in 0..old.len().max(new.len()) {
+                let pointer = format!("{}/{}", pointer, idx);
+                let (old, new) = (old.get(idx), new.get(idx));
+                walk(old, new, String::new(), &pointer, depth + 1, options, rows);
+            }
+            rows.push(row(String::new(), Some("]".into()), Some("]".into()), None));
+        }
+        (Some(old), Some(new)) => rows.push(row(
+            label,
+            Some(old.to_string()),
+            Some(new.to_string()),
+            Some(ChangeKind::Changed),
+        )),
+        (Some(old), None) => rows.push(row(
+            label,
+            Some(old.to_string()),
+            None,
+            Some(ChangeKind::Removed),
+        )),
+        (None, Some(new)) => rows.push(row(
+            label,
+            None,
+            Some(new.to_string()),
+            Some(ChangeKind::Added),
+        )),
+        (None, None) => {}
+    }
+}
+
+/// undo in `document` the change at `pointer`, with the node of `original`
+pub fn revert(
+    document: &mut Value,
+    original: &Value,
+    pointer: &str,
+    change: ChangeKind,
+) -> Result<(), String> {
+    let missing = || format!("no node at {}", display_pointer(pointer));
+    match change {
+        ChangeKind::Added => remove_node(document, pointer)
+            .map(|_| ())
+            .ok_or_else(missing),
+        ChangeKind::Changed => {
+            let value = original.pointer(pointer).ok_or_else(missing)?.clone();
+            *document.pointer_mut(pointer).ok_or_else(missing)? = value;
+            Ok(())
+        }
+        ChangeKind::Removed => {
+            let value = original.pointer(pointer).ok_or_else(missing)?.clone();
+            let (parent, token) = split_pointer(pointer).ok_or_else(missing)?;
+            match (document.pointer_mut(parent), original.pointer(parent)) {
+                (Some(Value::Object(obj)), Some(Value::Object(before))) => {
+                    // back after the members that were before it
+                    let position = before
+                        .keys()
+                        .take_while(|key| **key != token)
+                        .filter(|key| obj.contains_key(*key))
+                        .count();
+                    obj.shift_insert(position, token, value);
+                    Ok(())
+                }
+                (Some(Value::Array(array)), _) => {
+                    let idx = token.parse::<usize>().map_err(|e| e.to_string())?;
+                    array.insert(idx.min(array.len()), value);
+                    Ok(())
+                }
+                _ => Err(missing()),
+            }
+        }
+    }
+}
+
+/// the changes made since the file was loaded or saved
+pub struct DiffView {
+    pub rows: Vec<DiffRow>,
+    pub selected: usize, // the row of the selected change
+    pub side_by_side: bool,
+}
+
+impl DiffView {
+    pub fn changes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| row.change.is_some())
+            .map(|(idx, _)| idx)
+    }
+
+    /// select the next change, or the previous one
+    pub fn step(&mut self, forward: bool) {
+        let next = if forward {
+            self.changes().find(|idx| *idx > self.selected)
+        } else {
+            self.changes().filter(|idx| *idx < self.selected).last()
+        };
+        if let Some(next) = next {
+            self.selected = next;
+        }
+    }
+}
+
+impl App<'_> {
+    fn diff_view(&self, side_by_side: bool) -> DiffView {
+        let rows = diff_rows(
+            &self.original,
+            &self.json_container.inner,
+            &DiffOptions::default(),
+        );
+        let mut view = DiffView {
+            rows,
+            selected: 0,
+            side_by_side,
+        };
+        let first = view.changes().next();
+        view.selected = first.unwrap_or_default();
+        view
+    }
+
+    pub fn open_diff(&mut self) {
+        let view = self.diff_view(false);
+        if view.changes().next().is_none() {
+            self.status = Some("No changes".to_string());
+            return;
+        }
+        self.diff = Some(view);
+    }
+
+    pub fn handle_diff_key(&mut self, key: KeyEvent) {
+        let Some(view) = &mut self.diff else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.diff = None,
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('n') => view.step(true),
+            KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('p') => view.step(false),
+            KeyCode::Tab => view.side_by_side = !view.side_by_side,
+            KeyCode::Enter => {
+                let row = &view.rows[view.selected];
+                // a removed node is not in the document anymore, go to its parent
+                let pointer = match row.change {
+                    Some(ChangeKind::Removed) => split_pointer(&row.pointer)
+                        .map_or(String::new(), |(parent, _)| parent.to_string()),
+                    _ => row.pointer.clone(),
+                };
+                self.diff = None;
+                self.select_pointer(&pointer);
+            }
+            KeyCode::Char('r') if self.readonly => {
+                self.read_only();
+            }
+            KeyCode::Char('r') => {
+                let row = &view.rows[view.selected];
+                let (Some(change), pointer) = (row.change, row.pointer.clone()) else {
+                    return;
+                };
+                let (side_by_side, selected) = (view.side_by_side, view.selected);
+                match revert(
+                    &mut self.json_container.inner,
+                    &self.original,
+                    &pointer,
+                    change,
+                ) {
+                    Ok(()) => {
+                        self.json_container.mark_dirty();
+                        let restored = self.original.pointer(&pointer).cloned();
+                        self.record(match (change, restored) {
+                            (ChangeKind::Added, _) | (_, None) => Operation::Remove {
+                                path: pointer.clone(),
+                            },
+                            (ChangeKind::Changed, Some(value)) => Operation::Replace {
+                                path: pointer.clone(),
+                                value,
+                            },
+                            (ChangeKind::Removed, Some(value)) => Operation::Add {
+                                path: pointer.clone(),
+                                value,
+                            },
+                        });
+                        self.status = Some(format!("Reverted {}", display_pointer(&pointer)));
+                        let mut view = self.diff_view(side_by_side);
+                        // stay around the reverted change
+                        let next = view
+                            .changes()
+                            .find(|idx| *idx >= selected)
+                            .or_else(|| view.changes().last());
+                        // closed once everything is reverted
+                        self.diff = next.map(|next| {
+                            view.selected = next;
+                            view
+                        });
+                    }
+                    Err(e) => self.status = Some(format!("Cannot revert: {}", e)),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// one side of the tree, with the line of each row to scroll to the selection
+    pub fn diff_lines(&self, view: &DiffView, side: Option<bool>) -> (Vec<Line<'static>>, usize) {
+        let mut lines = Vec::new();
+        let mut selected_line = 0;
+        for (idx, row) in view.rows.iter().enumerate() {
+            if idx == view.selected {
+                selected_line = lines.len();
+            }
+            let indent = "    ".repeat(row.depth);
+            let style = row
+                .change
+                .map_or(Style::default(), |change| change.style(&self.theme));
+            let style = if idx == view.selected {
+                style.patch(self.theme.cursor)
+            } else {
+                style
+            };
+            let mut line = |marker: &str, text: &Option<String>| {
+                let Some(text) = text else {
+                    lines.push(Line::default());
+                    return;
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(marker.to_string(), style),
+                    Span::raw(indent.clone()),
+                    Span::styled(format!("{}{}", row.label, text), style),
+                ]));
+            };
+            match (side, row.change) {
+                // side by side, a missing node leaves its line empty
+                (Some(true), Some(ChangeKind::Added)) => line(" ", &None),
+                (Some(false), Some(ChangeKind::Removed)) => line(" ", &None),
+                (Some(true), _) => line(" ", &row.old),
+                (Some(false), _) => line(" ", &row.new),
+                (None, None) => line("  ", &row.new),
+                (None, Some(ChangeKind::Added)) => line("+ ", &row.new),
+                (None, Some(ChangeKind::Removed)) => line("- ", &row.old),
+                (None, Some(ChangeKind::Changed)) => {
+                    line("- ", &row.old);
+                    line("+ ", &row.new);
+                }
+            }
+        }
+        (lines, selected_line)
+    }
+
+    pub fn render_diff(&self, frame: &mut Frame) {
+        let Some(view) = &self.diff else {
+            return;
+        };
+        let title = format!(
+            "{} change(s) since the file was loaded",
+            view.changes().count()
+        );
+        let keys =
+            "Up/Down between changes, r to revert, Enter to go to, Tab for the layout, Esc to close";
+        self.render_diff_view(frame, view, title, keys, ["Loaded", "Edited"]);
+    }
+
+    /// a popup with the tree of `view`, unified or with the `sides` side by side
+    pub fn render_diff_view(
+        &self,
+        frame: &mut Frame,
+        view: &DiffView,
+        title: String,
+        keys: &str,
+        sides: [&str; 2],
+    ) {
+        let area = centered_rect(90, 80, frame.area());
+        frame.render_widget(Clear, area);
+        let block = Block::default()
+            .title(title)
+            .title_bottom(keys)
+            .borders(Borders::ALL)
+            .style(self.theme.popup);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let render = |frame: &mut Frame, lines: Vec<Line<'static>>, selected: usize, area: Rect| {
+            // keep the selected change in the middle
+            let scroll = selected.saturating_sub(area.height as usize / 2) as u16;
+            frame.render_widget(Paragraph::new(lines).scroll((scroll, 0)), area);
+        };
+        if view.side_by_side {
+            let halves = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(inner);
+            for ((side, half), title) in [(true, halves[0]), (false, halves[1])]
+                .into_iter()
+                .zip(sides)
+            {
+                let (lines, selected) = self.diff_lines(view, Some(side));
+                let block = Block::default().title(title).borders(Borders::TOP);
+                render(frame, lines, selected, block.inner(half));
+                frame.render_widget(block, half);
+            }
+        } else {
+            let (lines, selected) = self.diff_lines(view, None);
+            render(frame, lines, selected, inner);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_diff_and_revert() {
+        let original = json!({"a": 1, "b": {"c": [1, 2], "d": "x"}, "e": true});
+        let mut edited = json!({"b": {"d": "y", "c": [1, 2, 3]}, "e": true, "f": null});
+        let changes: Vec<(String, ChangeKind)> = diff_rows(&original, &edited, &Default::default())
+            .into_iter()
+            .filter_map(|row| Some((row.pointer, row.change?)))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("/b/d".to_string(), ChangeKind::Changed),
+                ("/b/c/2".to_string(), ChangeKind::Added),
+                ("/f".to_string(), ChangeKind::Added),
+                ("/a".to_string(), ChangeKind::Removed),
+            ]
+        );
+        for (pointer, change) in changes {
+            revert(&mut edited, &original, &pointer, change).unwrap();
+        }
+        assert_eq!(edited, original);
+        assert_eq!(
+            edited.to_string(),
+            r#"{"a":1,"b":{"d":"x","c":[1,2]},"e":true}"#
+        );
+    }
+}
@@ -0,0 +1,403 @@
+use serde_json::{Map, Value};
+
+use crate::{
+    app::App,
+    json::{compare_values, count_inexact_numbers, escape_pointer_token},
+    patch::Operation,
+};
+
+/// unescape a JSON Pointer (RFC 6901) token
+pub fn unescape_pointer_token(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// split a pointer into the pointer of its parent and its last token, `None` for the root
+pub fn split_pointer(pointer: &str) -> Option<(&str, String)> {
+    let pos = pointer.rfind('/')?;
+    Some((&pointer[..pos], unescape_pointer_token(&pointer[pos + 1..])))
+}
+
+/// a JSON Pointer (`/a/0`) as is, or a dotted path (`a.0`) as a pointer
+pub fn path_to_pointer(path: &str) -> String {
+    if path.is_empty() || path.starts_with('/') {
+        return path.to_string();
+    }
+    path.trim_start_matches('.')
+        .split('.')
+        .map(|token| format!("/{}", escape_pointer_token(token)))
+        .collect()
+}
+
+/// `key`, or `key_2`, `key_3`... if it is already used in `obj`
+pub fn unique_key(obj: &Map<String, Value>, key: &str) -> String {
+    if !obj.contains_key(key) {
+        return key.to_string();
+    }
+    (2..)
+        .map(|i| format!("{}_{}", key, i))
+        .find(|candidate| !obj.contains_key(candidate))
+        .unwrap_or_default()
+}
+
+/// remove the node at `pointer`, returns its key when it was an object member, and its value
+pub fn remove_node(root: &mut Value, pointer: &str) -> Option<(Option<String>, Value)> {
+    let (parent, token) = split_pointer(pointer)?;
+    match root.pointer_mut(parent)? {
+        Value::Array(array) => {
+            let idx = token
+                .parse::<usize>()
+                .ok()
+                .filter(|idx| *idx < array.len())?;
+            Some((None, array.remove(idx)))
+        }
+        Value::Object(obj) => obj
+            .shift_remove_entry(&token)
+            .map(|(key, value)| (Some(key), value)),
+        _ => None,
+    }
+}
+
+/// insert `value` right after the node at `pointer` in its parent, or at the end of the
+/// root when `pointer` is the root; `key` is used, made unique, when the parent is an object.
+/// Returns the pointer of the inserted node.
+pub fn insert_after(
+    root: &mut Value,
+    pointer: &str,
+    key: Option<String>,
+    value: Value,
+) -> Result<String, String> {
+    let (parent, position) = match split_pointer(pointer) {
+        Some((parent, token)) => (parent, Some(token)),
+        None => ("", None),
+    };
+    let key = key.unwrap_or_else(|| "new".to_string());
+    match root.pointer_mut(parent) {
+        Some(Value::Array(array)) => {
+            let idx = match position {
+                Some(token) => token.parse::<usize>().map_err(|e| e.to_string())? + 1,
+                None => array.len(),
+            };
+            let idx = idx.min(array.len());
+            array.insert(idx, value);
+            Ok(format!("{}/{}", parent, idx))
+        }
+        Some(Value::Object(obj)) => {
+            let idx = match position {
+                Some(token) => obj
+                    .keys()
+                    .position(|k| *k == token)
+                    .map_or(obj.len(), |i| i + 1),
+                None => obj.len(),
+            };
+            let key = unique_key(obj, &key);
+            obj.shift_insert(idx, key.clone(), value);
+            Ok(format!("{}/{}", parent, escape_pointer_token(&key)))
+        }
+        Some(_) => Err("can only insert into an array or an object".to_string()),
+        None => Err(format!("no node at \"{}\"", parent)),
+    }
+}
+
+/// rename the object member at `pointer`, keeping its position; returns its new pointer
+pub fn rename_key(root: &mut Value, pointer: &str, new_key: &str) -> Result<String, String> {
+    let (parent, key) = split_pointer(pointer).ok_or("the root has no key")?;
+    let Some(Value::Object(obj)) = root.pointer_mut(parent) else {
+        return Err("only object members have a key".to_string());
+    };
+    if key != new_key && obj.contains_key(new_key) {
+        return Err(format!("the key \"{}\" already exists", new_key));
+    }
+    let idx = obj.keys().position(|k| *k == key).ok_or("no such key")?;
+    if let Some((_, value)) = obj.shift_remove_entry(&key) {
+        obj.shift_insert(idx, new_key.to_string(), value);
+    }
+    Ok(format!("{}/{}", parent, escape_pointer_token(new_key)))
+}
+
+/// swap the node at `pointer` with its previous or next sibling; returns its new pointer
+pub fn move_node(root: &mut Value, pointer: &str, down: bool) -> Option<String> {
+    let (parent, token) = split_pointer(pointer)?;
+    match root.pointer_mut(parent)? {
+        Value::Array(array) => {
+            let idx = token.parse::<usize>().ok()?;
+            let other = if down { idx + 1 } else { idx.checked_sub(1)? };
+            if other >= array.len() {
+                return None;
+            }
+            array.swap(idx, other);
+            Some(format!("{}/{}", parent, other))
+        }
+        Value::Object(obj) => {
+            let idx = obj.keys().position(|k| *k == token)?;
+            let other = if down { idx + 1 } else { idx.checked_sub(1)? };
+            if other >= obj.len() {
+                return None;
+            }
+            let (key, value) = obj.shift_remove_entry(&token)?;
+            obj.shift_insert(other, key, value);
+            Some(pointer.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// sort the keys of an object alphabetically, and of the objects it contains when `recursive`
+pub fn sort_keys(value: &mut Value, recursive: bool) {
+    match value {
+        Value::Object(obj) => {
+            obj.sort_keys();
+            if recursive {
+                obj.values_mut().for_each(|value| sort_keys(value, true));
+            }
+        }
+        Value::Array(array) if recursive => {
+            array.iter_mut().for_each(|value| sort_keys(value, true));
+        }
+        _ => {}
+    }
+}
+
+/// sort an array by its values, or by the value at `field` (a pointer) in each element
+pub fn sort_array(array: &mut [Value], field: &str) {
+    array.sort_by(|a, b| compare_values(a.pointer(field), b.pointer(field)));
+}
+
+impl App<'_> {
+    /// the key of the selected node, when it is an object member
+    pub fn selected_key(&self) -> Option<String> {
+        let pointer = self.selected_pointer();
+        split_pointer(&pointer)
+            .filter(|(parent, _)| {
+                self.json_container
+                    .inner
+                    .pointer(parent)
+                    .is_some_and(Value::is_object)
+            })
+            .map(|(_, key)| key)
+    }
+
+    pub fn rename_selected(&mut self, new_key: &str) {
+        let pointer = self.selected_pointer();
+        match rename_key(&mut self.json_container.inner, &pointer, new_key) {
+            Ok(renamed) => {
+                self.json_container.mark_dirty();
+                self.record(Operation::Move {
+                    from: pointer,
+                    path: renamed.clone(),
+                });
+                self.select_pointer(&renamed);
+            }
+            Err(e) => self.status = Some(format!("Cannot rename: {}", e)),
+        }
+    }
+
+    pub fn move_selected(&mut self, down: bool) {
+        let pointer = self.selected_pointer();
+        if let Some(moved) = move_node(&mut self.json_container.inner, &pointer, down) {
+            self.json_container.mark_dirty();
+            // the order of object members is not part of a patch
+            if moved != pointer {
+                self.record(Operation::Move {
+                    from: pointer,
+                    path: moved.clone(),
+                });
+            }
+            self.select_pointer(&moved);
+        }
+    }
+
+    /// sort the keys of the selected object, or of the object containing the selected node
+    pub fn sort_selected_keys(&mut self, recursive: bool) {
+        let pointer = self.focused_container_pointer();
+        match self.json_container.inner.pointer_mut(&pointer) {
+            Some(value) if value.is_object() || recursive => {
+                sort_keys(value, recursive);
+                let sorted = value.clone();
+                self.json_container.mark_dirty();
+                self.record(Operation::Replace {
+                    path: pointer.clone(),
+                    value: sorted,
+                });
+                self.status = Some(format!("Sorted the keys of {}", display_pointer(&pointer)));
+            }
+            _ => self.status = Some("Not an object".to_string()),
+        }
+    }
+
+    /// sort the selected array, or the array containing the selected node, by value or
+    /// by the `field` (a pointer or a dotted path) of its elements
+    pub fn sort_selected_array(&mut self, field: &str) {
+        let pointer = self.focused_container_pointer();
+        let field = path_to_pointer(field.trim());
+        let Some(Value::Array(array)) = self.json_container.inner.pointer_mut(&pointer) else {
+            self.status = Some("Not an array".to_string());
+            return;
+        };
+        sort_array(array, &field);
+        let inexact: usize = array
+            .iter()
+            .filter_map(|element| element.pointer(&field))
+            .map(count_inexact_numbers)
+            .sum();
+        let sorted = Value::Array(array.clone());
+        self.json_container.mark_dirty();
+        self.record(Operation::Replace {
+            path: pointer.clone(),
+            value: sorted,
+        });
+        self.status = Some(if inexact > 0 {
+            format!(
+                "Warning: {} number(s) compared as rounded 64-bit floats",
+                inexact
+            )
+        } else {
+            format!("Sorted {}", display_pointer(&pointer))
+        });
+    }
+
+    pub fn delete_selected(&mut self) {
+        let pointer = self.selected_pointer();
+        if pointer.is_empty() {
+            self.status = Some("Cannot delete the root".to_string());
+            return;
+        }
+        if remove_node(&mut self.json_container.inner, &pointer).is_none() {
+            return;
+        }
+        self.json_container.mark_dirty();
+        self.record(Operation::Remove {
+            path: pointer.clone(),
+        });
+        // keep the selection on the node that took the place of the deleted one
+        let next = match split_pointer(&pointer) {
+            Some((parent, _)) if self.json_container.inner.pointer(&pointer).is_none() => {
+                parent.to_string()
+            }
+            _ => pointer,
+        };
+        match next.as_str() {
+            "" => {
+                let len = self.json_container.len().unwrap_or_default();
+                self.index_edition = self
+                    .index_edition
+                    .filter(|_| len > 0)
+                    .map(|idx| idx.min(len - 1));
+                self.reset_cursor();
+            }
+            next => self.select_pointer(next),
+        }
+    }
+
+    pub fn yank_selected(&mut self) {
+        let pointer = self.selected_pointer();
+        let key = self.selected_key();
+        if let Some(value) = self.json_container.inner.pointer(&pointer) {
+            // kept in the app, and put in the system clipboard for other programs
+            self.register = Some((key, value.clone()));
+            self.yanked_from = Some(pointer.clone());
+            if let Ok(text) = serde_json::to_string_pretty(value) {
+                self.copy_to_clipboard(&text);
+            }
+            self.status = Some(format!("Copied {}", display_pointer(&pointer)));
+        }
+    }
+
+    pub fn paste_after_selected(&mut self) {
+        let Some((key, value)) = self.register.clone() else {
+            self.status = Some("Nothing to paste".to_string());
+            return;
+        };
+        // still in place, the pasted node is a copy of it
+        let from = self
+            .yanked_from
+            .clone()
+            .filter(|from| self.json_container.inner.pointer(from) == Some(&value));
+        self.insert_after_selected(key, value, from);
+    }
+
+    /// insert `value`, copied `from` a node of the document or not, after the selected
+    /// node, and select it
+    pub fn insert_after_selected(
+        &mut self,
+        key: Option<String>,
+        value: Value,
+        from: Option<String>,
+    ) {
+        let pointer = self.selected_pointer();
+        match insert_after(&mut self.json_container.inner, &pointer, key, value.clone()) {
+            Ok(inserted) => {
+                self.json_container.mark_dirty();
+                self.record(match from {
+                    Some(from) => Operation::Copy {
+                        from,
+                        path: inserted.clone(),
+                    },
+                    None => Operation::Add {
+                        path: inserted.clone(),
+                        value,
+                    },
+                });
+                self.select_pointer(&inserted);
+            }
+            Err(e) => self.status = Some(format!("Cannot paste: {}", e)),
+        }
+    }
+}
+
+/// the root pointer is empty, show it as `/`
+pub fn display_pointer(pointer: &str) -> &str {
+    if pointer.is_empty() {
+        "/"
+    } else {
+        pointer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_reorder() {
+        let mut root = json!({"b": 1, "a": {"d": 2, "c": [3, 1, 2]}});
+        assert_eq!(rename_key(&mut root, "/b", "e"), Ok("/e".to_string()));
+        assert!(rename_key(&mut root, "/e", "a").is_err());
+        assert_eq!(move_node(&mut root, "/e", true), Some("/e".to_string()));
+        assert_eq!(
+            move_node(&mut root, "/a/c/0", true),
+            Some("/a/c/1".to_string())
+        );
+        assert_eq!(move_node(&mut root, "/a/c/2", true), None);
+        assert_eq!(root.to_string(), r#"{"a":{"d":2,"c":[1,3,2]},"e":1}"#);
+
+        sort_keys(&mut root, true);
+        assert_eq!(root.to_string(), r#"{"a":{"c":[1,3,2],"d":2},"e":1}"#);
+
+        // sorting keys is recorded, to be exported with the other edits
+        let mut app = App::default();
+        app.json_container.inner = json!({"b": 1, "a": 2});
+        app.sort_selected_keys(false);
+        assert_eq!(
+            app.patch,
+            [Operation::Replace {
+                path: String::new(),
+                value: json!({"a": 2, "b": 1})
+            }]
+        );
+        assert_eq!(
+            crate::patch::apply_patch(&json!({"b": 1, "a": 2}), &app.patch)
+                .unwrap()
+                .to_string(),
+            r#"{"a":2,"b":1}"#
+        );
+
+        let mut array = vec![json!({"n": 2}), json!({"n": 10}), json!({"m": 1})];
+        sort_array(&mut array, "/n");
+        assert_eq!(
+            Value::from(array).to_string(),
+            r#"[{"m":1},{"n":2},{"n":10}]"#
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+    process::Command,
+};
+
+use ratatui::{backend::Backend, Terminal};
+use serde_json::Value;
+
+use crate::{
+    app::{create_unique, App},
+    edit::display_pointer,
+    enter_tui, leave_tui,
+};
+
+/// the editor to run, from `$VISUAL` or `$EDITOR`, split in its program and arguments
+pub fn editor_command() -> Vec<String> {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+impl App<'_> {
+    /// edit the node at `pointer` in an external editor, the interface is suspended until
+    /// it exits; the editor is opened again while the file is not valid JSON
+    pub fn edit_externally<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        pointer: &str,
+    ) -> io::Result<()> {
+        let Some(value) = self.json_container.inner.pointer(pointer) else {
+            return Ok(());
+        };
+        let (path, mut file) = create_unique(&std::env::temp_dir(), "json-editor", "json")?;
+        let written = file.write_all(serde_json::to_string_pretty(value)?.as_bytes());
+        drop(file);
+        if let Err(e) = written {
+            let _ = std::fs::remove_file(&path);
+            return Err(e);
+        }
+
+        leave_tui()?;
+        let result = run_editor(&path);
+        let _ = std::fs::remove_file(&path);
+        enter_tui(!self.no_mouse)?;
+        terminal.clear()?;
+
+        match result {
+            Ok(value) => {
+                self.replace_at(pointer, value);
+                self.select_pointer(pointer);
+                self.status = Some(format!("Edited {}", display_pointer(pointer)));
+            }
+            Err(e) => self.status = Some(format!("Edit cancelled: {}", e)),
+        }
+        Ok(())
+    }
+}
+
+/// the first line of a file reopened after an error, removed before parsing it again
+const ERROR_MARK: &str = "// json-editor: ";
+
+/// run the editor on `path` until it holds valid JSON, or is left unchanged after an error;
+/// the error is written at the top of the file when it is reopened
+fn run_editor(path: &Path) -> Result<Value, String> {
+    let command = editor_command();
+    let mut previous: Option<String> = None;
+    loop {
+        let status = Command::new(&command[0])
+            .args(&command[1..])
+            .arg(path)
+            .status()
+            .map_err(|e| format!("cannot run {}: {}", command[0], e))?;
+        if !status.success() {
+            return Err(format!("{} exited with {}", command[0], status));
+        }
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let text = match text.strip_prefix(ERROR_MARK) {
+            Some(rest) => rest
+                .split_once('\n')
+                .map_or("", |(_, json)| json)
+                .to_string(),
+            None => text,
+        };
+        match serde_json::from_str(&text) {
+            Ok(value) => return Ok(value),
+            // saved again without a fix, give up
+            Err(e) if previous.as_ref() == Some(&text) => {
+                return Err(format!("invalid JSON: {}", e))
+            }
+            Err(e) => {
+                let error = format!(
+                    "{}invalid JSON: {}, save it unchanged to cancel\n",
+                    ERROR_MARK, e
+                );
+                std::fs::write(path, error + &text).map_err(|e| e.to_string())?;
+                previous = Some(text);
+            }
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::{Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::{
+    app::App,
+    keymap::{Action, Context, KeyMap},
+    ui::centered_rect,
+};
+
+/// the help popup, listing the actions of the screen it was opened from
+pub struct Help {
+    pub context: Context,
+    pub search: String,
+    pub scroll: usize,
+}
+
+impl Help {
+    pub fn new(context: Context) -> Self {
+        Self {
+            context,
+            search: String::new(),
+            scroll: 0,
+        }
+    }
+
+    /// the keys and description of every action matching the search, the bound actions
+    /// first in the order of their bindings and then the unbound actions of the context
+    fn rows(&self, keymap: &KeyMap) -> Vec<(String, Action)> {
+        let search = self.search.to_lowercase();
+        let mut actions: Vec<Action> = Vec::new();
+        let bound = keymap
+            .bindings(self.context)
+            .iter()
+            .map(|(_, action)| *action);
+        for action in bound.chain(self.context.actions().iter().copied()) {
+            if !actions.contains(&action) {
+                actions.push(action);
+            }
+        }
+        actions
+            .into_iter()
+            .map(|action| {
+                let keys = keymap.keys_for(self.context, action).join(", ");
+                (keys, action)
+            })
+            .filter(|(keys, action)| {
+                search.is_empty()
+                    || keys.to_lowercase().contains(&search)
+                    || action.name().contains(&search)
+                    || action.description().to_lowercase().contains(&search)
+            })
+            .collect()
+    }
+}
+
+impl App<'_> {
+    pub fn open_help(&mut self) {
+        let context = self.key_context().unwrap_or(Context::Main);
+        self.help = Some(Help::new(context));
+    }
+
+    pub fn handle_help_key(&mut self, key: KeyEvent) {
+        let Some(help) = &mut self.help else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc | KeyCode::F(1) => self.help = None,
+            KeyCode::Char('?') if help.search.is_empty() => self.help = None,
+            KeyCode::Up => help.scroll = help.scroll.saturating_sub(1),
+            KeyCode::Down => help.scroll = help.scroll.saturating_add(1),
+            KeyCode::Backspace => {
+                help.search.pop();
+                help.scroll = 0;
+            }
+            KeyCode::Char(value) => {
+                help.search.push(value);
+                help.scroll = 0;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn render_help(&mut self, frame: &mut Frame) {
+        let Some(help) = &mut self.help else {
+            return;
+        };
+        let area = centered_rect(70, 70, frame.area());
+        frame.render_widget(Clear, area);
+        let block = Block::default()
+            .title(format!("Help - {} (Esc to close)", help.context.name()))
+            .borders(Borders::ALL)
+            .style(self.theme.popup);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(2), Constraint::Min(1)])
+            .split(inner);
+
+        let search = Line::from(vec![
+            Span::styled("Search: ", self.theme.hint),
+            Span::raw(help.search.clone()),
+        ]);
+        frame.render_widget(Paragraph::new(search), chunks[0]);
+
+        let rows = help.rows(&self.keymap);
+        let key_width = rows
+            .iter()
+            .map(|(keys, _)| keys.chars().count())
+            .max()
+            .unwrap_or_default()
+            .max("(unbound)".len());
+        help.scroll = help
+            .scroll
+            .min(rows.len().saturating_sub(chunks[1].height as usize));
+        let lines: Vec<Line> = rows
+            .into_iter()
+            .skip(help.scroll)
+            .map(|(keys, action)| {
+                let keys = if keys.is_empty() {
+                    "(unbound)".to_string()
+                } else {
+                    keys
+                };
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:width$}  ", keys, width = key_width),
+                        self.theme.key,
+                    ),
+                    Span::raw(action.description()),
+                ])
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines), chunks[1]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::keymap::KeysConfig;
+
+    use super::*;
+
+    #[test]
+    fn test_rows_follow_bindings() {
+        let config = KeysConfig {
+            preset: Some("vim".to_string()),
+            ..Default::default()
+        };
+        let keymap = KeyMap::new(&config).unwrap();
+        let rows = Help::new(Context::Main).rows(&keymap);
+        assert!(rows.contains(&("Z Z".to_string(), Action::QuitAndWrite)));
+        assert!(rows.iter().any(|(_, action)| *action == Action::Quit));
+
+        let mut help = Help::new(Context::Exiting);
+        assert!(help.rows(&keymap).iter().any(|(_, a)| *a == Action::Help));
+        help.search = "writ".to_string();
+        let actions: Vec<Action> = help.rows(&keymap).into_iter().map(|(_, a)| a).collect();
+        assert_eq!(actions, [Action::QuitAndWrite, Action::QuitWithoutWriting]);
+    }
+}
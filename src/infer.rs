@@ -0,0 +1,222 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde_json::{Map, Value};
+
+use crate::app::App;
+
+/// strings with at most this many distinct values become an enum, when at least one value
+/// repeats
+const ENUM_LIMIT: usize = 5;
+
+static FORMATS: LazyLock<[(&str, Regex); 3]> = LazyLock::new(|| {
+    [
+        (
+            "date-time",
+            Regex::new(r"^\d{4}-\d{2}-\d{2}[Tt ]\d{2}:\d{2}:\d{2}(\.\d+)?([Zz]|[+-]\d{2}:\d{2})$")
+                .unwrap(),
+        ),
+        ("email", Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").unwrap()),
+        (
+            "uri",
+            Regex::new(r"^[A-Za-z][A-Za-z0-9+.-]*://[^\s]+$").unwrap(),
+        ),
+    ]
+});
+
+/// the format of a string, as named by JSON Schema
+fn format_of(text: &str) -> Option<&'static str> {
+    FORMATS
+        .iter()
+        .find(|(_, regex)| regex.is_match(text))
+        .map(|(name, _)| *name)
+}
+
+/// what the samples seen at one place of the document have in common
+#[derive(Default)]
+struct Shape {
+    null: bool,
+    boolean: bool,
+    integer: bool,
+    number: bool,
+    strings: usize,
+    format: Option<Option<&'static str>>, // the format shared by every string, once one was seen
+    distinct: Vec<String>,                // up to ENUM_LIMIT + 1 distinct strings
+    items: Option<Box<Shape>>,            // the merged elements, once an array was seen
+    objects: usize,
+    properties: Vec<(String, usize, Shape)>, // with the number of objects having the key
+}
+
+impl Shape {
+    fn add(&mut self, value: &Value) {
+        match value {
+            Value::Null => self.null = true,
+            Value::Bool(_) => self.boolean = true,
+            Value::Number(number) if number.is_i64() || number.is_u64() => self.integer = true,
+            Value::Number(_) => self.number = true,
+            Value::String(text) => {
+                self.strings += 1;
+                let format = format_of(text);
+                self.format = Some(match self.format {
+                    Some(shared) if shared != format => None,
+                    _ => format,
+                });
+                if self.distinct.len() <= ENUM_LIMIT && !self.distinct.contains(text) {
+                    self.distinct.push(text.clone());
+                }
+            }
+            Value::Array(array) => {
+                let items = self.items.get_or_insert_with(Default::default);
+                for item in array {
+                    items.add(item);
+                }
+            }
+            Value::Object(obj) => {
+                self.objects += 1;
+                for (key, item) in obj {
+                    let position = match self.properties.iter().position(|(name, ..)| name == key) {
+                        Some(position) => position,
+                        None => {
+                            self.properties.push((key.clone(), 0, Shape::default()));
+                            self.properties.len() - 1
+                        }
+                    };
+                    let (_, count, shape) = &mut self.properties[position];
+                    *count += 1;
+                    shape.add(item);
+                }
+            }
+        }
+    }
+
+    fn to_schema(&self) -> Value {
+        let mut types = Vec::new();
+        if self.null {
+            types.push("null");
+        }
+        if self.boolean {
+            types.push("boolean");
+        }
+        match (self.integer, self.number) {
+            (_, true) => types.push("number"),
+            (true, false) => types.push("integer"),
+            _ => {}
+        }
+        if self.strings > 0 {
+            types.push("string");
+        }
+        if self.items.is_some() {
+            types.push("array");
+        }
+        if self.objects > 0 {
+            types.push("object");
+        }
+
+        let mut schema = Map::new();
+        match types.as_slice() {
+            [] => {}
+            [name] => {
+                schema.insert("type".to_string(), Value::from(*name));
+            }
+            names => {
+                schema.insert("type".to_string(), Value::from(names.to_vec()));
+            }
+        }
+        if let Some(Some(format)) = self.format {
+            schema.insert("format".to_string(), Value::from(format));
+        } else if types == ["string"]
+            && self.distinct.len() <= ENUM_LIMIT
+            && self.strings > self.distinct.len()
+        {
+            schema.insert("enum".to_string(), Value::from(self.distinct.clone()));
+        }
+        if let Some(items) = &self.items {
+            let items = items.to_schema();
+            if items.as_object().is_some_and(|items| !items.is_empty()) {
+                schema.insert("items".to_string(), items);
+            }
+        }
+        if self.objects > 0 {
+            let properties: Map<String, Value> = self
+                .properties
+                .iter()
+                .map(|(key, _, shape)| (key.clone(), shape.to_schema()))
+                .collect();
+            let required: Vec<Value> = self
+                .properties
+                .iter()
+                .filter(|(_, count, _)| *count == self.objects)
+                .map(|(key, ..)| Value::from(key.clone()))
+                .collect();
+            schema.insert("properties".to_string(), Value::Object(properties));
+            if !required.is_empty() {
+                schema.insert("required".to_string(), Value::Array(required));
+            }
+        }
+        Value::Object(schema)
+    }
+}
+
+/// a draft JSON Schema describing `value`: the elements of arrays are merged, the keys
+/// found in every object are required
+pub fn infer_schema(value: &Value) -> Value {
+    let mut shape = Shape::default();
+    shape.add(value);
+    let mut schema = Map::new();
+    schema.insert(
+        "$schema".to_string(),
+        Value::from("https://json-schema.org/draft/2020-12/schema"),
+    );
+    if let Value::Object(inferred) = shape.to_schema() {
+        schema.extend(inferred);
+    }
+    Value::Object(schema)
+}
+
+impl App<'_> {
+    pub fn export_schema(&mut self, path: &str) {
+        let schema = infer_schema(&self.json_container.inner);
+        let result = serde_json::to_string_pretty(&schema)
+            .map_err(|e| e.to_string())
+            .and_then(|text| std::fs::write(path, text + "\n").map_err(|e| e.to_string()));
+        self.status = Some(match result {
+            Ok(()) => format!("Exported the schema to {}", path),
+            Err(e) => format!("Export failed: {}", e),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_infer_schema() {
+        let document = json!([
+            {"id": 1, "kind": "a", "at": "2024-01-02T03:04:05Z", "site": "https://x.org"},
+            {"id": 2.5, "kind": "b", "at": "2024-01-03T00:00:00+01:00", "mail": "a@b.io"},
+            {"id": 3, "kind": "a", "at": null, "tags": []}
+        ]);
+        assert_eq!(
+            infer_schema(&document),
+            json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "id": {"type": "number"},
+                        "kind": {"type": "string", "enum": ["a", "b"]},
+                        "at": {"type": ["null", "string"], "format": "date-time"},
+                        "site": {"type": "string", "format": "uri"},
+                        "mail": {"type": "string", "format": "email"},
+                        "tags": {"type": "array"}
+                    },
+                    "required": ["id", "kind", "at"]
+                }
+            })
+        );
+    }
+}
@@ -1,20 +1,24 @@
-use std::io::{self, Write};
+use std::{cmp::Ordering, collections::HashSet};
 
 use ratatui::{
-    style::{Color, Stylize},
-    text::Line,
+    style::{Modifier, Style},
+    text::{Line, Span},
 };
-use serde::Serialize;
-use serde_json::ser::PrettyFormatter;
+
+use crate::{text_input::TextInput, theme::Theme};
 
 #[derive(Default)]
 pub struct JsonContainer<'a> {
     pub inner: serde_json::Value,
     pub lines: Vec<Line<'a>>,
     pub save_current_pos: Option<usize>,
-    pub input_buffer: String,
+    pub save_focused: String,
+    pub key_buffer: TextInput,
+    pub input_buffer: TextInput,
     pub input_cursor: Option<usize>,
     pub max_cursor: Option<usize>,
+    pub revision: u64,            // incremented on every change of `inner`
+    pub invalid: HashSet<String>, // pointers of the nodes not matching the schema
 }
 
 impl<'a> JsonContainer<'a> {
@@ -33,76 +37,364 @@ impl<'a> JsonContainer<'a> {
         self.save_current_pos == *current_pos
     }
 
-    pub fn create_lines(&mut self, current_pos: Option<usize>) {
-        if current_pos.is_some() && self.check_same_current_pos(&current_pos) {
+    pub fn create_lines(&mut self, current_pos: Option<usize>, focused: &str, theme: &Theme) {
+        if current_pos.is_some()
+            && self.check_same_current_pos(&current_pos)
+            && self.save_focused == focused
+        {
             // no need to re-render
             return;
         }
         self.save_current_pos.clone_from(&current_pos);
-        let mut writer = MyWriter::new(current_pos);
-        let formatter = PrettyFormatter::with_indent(b"    ");
-        let mut ser = serde_json::Serializer::with_formatter(&mut writer, formatter);
-        self.inner.serialize(&mut ser).unwrap();
-        writer.write_all(&[b'\n']).unwrap();
+        self.save_focused = focused.to_string();
+        let mut writer = MyWriter::new(current_pos, focused, theme);
+        writer.invalid = self.invalid.clone();
+        writer.write_value(&self.inner, "", 0);
+        writer.end_line();
         self.lines = writer.inner
     }
+
+    /// force the next `create_lines` to re-render, to be called after `inner` was modified
+    pub fn mark_dirty(&mut self) {
+        self.save_current_pos = None;
+        self.revision += 1;
+    }
+}
+
+/// split a decimal number into its sign, significant digits and exponent, such that
+/// the value is `0.digits * 10^exponent`, to compare numbers independently of their notation
+fn normalize_decimal(text: &str) -> Option<(bool, String, i64)> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(pos) => (&text[..pos], text[pos + 1..].parse::<i64>().ok()?),
+        None => (text, 0),
+    };
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{}{}", int_part, frac_part);
+    let leading_zeros = digits.len() - digits.trim_start_matches('0').len();
+    let digits = digits.trim_matches('0').to_string();
+    if digits.is_empty() {
+        return Some((false, digits, 0));
+    }
+    let exponent = exponent + int_part.len() as i64 - leading_zeros as i64;
+    Some((negative, digits, exponent))
 }
 
+/// whether two numbers have the same value: `1` and `1.0` are equal, integers above 2^53
+/// rounding to the same `f64` are not
+pub fn same_number(a: &serde_json::Number, b: &serde_json::Number) -> bool {
+    match (
+        normalize_decimal(&a.to_string()),
+        normalize_decimal(&b.to_string()),
+    ) {
+        (Some(a), Some(b)) => a == b,
+        _ => number_to_f64(a).0 == number_to_f64(b).0,
+    }
+}
+
+/// order two numbers by value, exactly even beyond the precision of `f64`
+pub fn compare_numbers(a: &serde_json::Number, b: &serde_json::Number) -> Ordering {
+    let (Some(a), Some(b)) = (
+        normalize_decimal(&a.to_string()),
+        normalize_decimal(&b.to_string()),
+    ) else {
+        return number_to_f64(a).0.total_cmp(&number_to_f64(b).0);
+    };
+    let sign = |(negative, digits, _): &(bool, String, i64)| match (digits.is_empty(), negative) {
+        (true, _) => 0,
+        (false, true) => -1,
+        (false, false) => 1,
+    };
+    // with the same exponent, `0.digits` compare like their digits
+    let magnitude = a.2.cmp(&b.2).then_with(|| a.1.cmp(&b.1));
+    match sign(&a).cmp(&sign(&b)) {
+        Ordering::Equal if sign(&a) < 0 => magnitude.reverse(),
+        Ordering::Equal if sign(&a) > 0 => magnitude,
+        order => order,
+    }
+}
+
+/// convert a number to `f64`, the boolean tells if the conversion is exact
+pub fn number_to_f64(number: &serde_json::Number) -> (f64, bool) {
+    let text = number.to_string();
+    let float = match text.parse::<f64>() {
+        Ok(float) if float.is_finite() => float,
+        _ => return (f64::NAN, false),
+    };
+    let exact = normalize_decimal(&text) == normalize_decimal(&format!("{:e}", float));
+    (float, exact)
+}
+
+fn type_rank(value: Option<&serde_json::Value>) -> u8 {
+    match value {
+        None => 0,
+        Some(serde_json::Value::Null) => 1,
+        Some(serde_json::Value::Bool(_)) => 2,
+        Some(serde_json::Value::Number(_)) => 3,
+        Some(serde_json::Value::String(_)) => 4,
+        Some(serde_json::Value::Array(_)) => 5,
+        Some(serde_json::Value::Object(_)) => 6,
+    }
+}
+
+/// order values for sorting: missing < null < booleans < numbers < strings < arrays < objects,
+/// numbers are compared as floats, then by their text so that the order is total
+pub fn compare_values(a: Option<&serde_json::Value>, b: Option<&serde_json::Value>) -> Ordering {
+    use serde_json::Value;
+    match (a, b) {
+        (Some(Value::Bool(a)), Some(Value::Bool(b))) => a.cmp(b),
+        (Some(Value::Number(a)), Some(Value::Number(b))) => number_to_f64(a)
+            .0
+            .total_cmp(&number_to_f64(b).0)
+            .then_with(|| a.to_string().cmp(&b.to_string())),
+        (Some(Value::String(a)), Some(Value::String(b))) => a.cmp(b),
+        (Some(a), Some(b)) if type_rank(Some(a)) == type_rank(Some(b)) => {
+            a.to_string().cmp(&b.to_string())
+        }
+        _ => type_rank(a).cmp(&type_rank(b)),
+    }
+}
+
+/// count the numbers in `value` that a 64-bit float cannot represent exactly
+pub fn count_inexact_numbers(value: &serde_json::Value) -> usize {
+    match value {
+        serde_json::Value::Number(number) => usize::from(!number_to_f64(number).1),
+        serde_json::Value::Array(array) => array.iter().map(count_inexact_numbers).sum(),
+        serde_json::Value::Object(obj) => obj.values().map(count_inexact_numbers).sum(),
+        _ => 0,
+    }
+}
+
+/// escape a key to be used as a JSON Pointer (RFC 6901) token
+pub fn escape_pointer_token(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// collect the JSON Pointer of every node that `render_json_struct` gives a cursor index to,
+/// in the same order, so the node under `input_cursor` is `pointers[input_cursor - 1]`
+pub fn node_pointers(value: &serde_json::Value, prefix: &str, pointers: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Array(array) => {
+            for (i, value) in array.iter().enumerate() {
+                let pointer = format!("{}/{}", prefix, i);
+                pointers.push(pointer.clone());
+                node_pointers(value, &pointer, pointers);
+            }
+        }
+        serde_json::Value::Object(obj) => {
+            for (key, value) in obj.iter() {
+                let pointer = format!("{}/{}", prefix, escape_pointer_token(key));
+                if !value.is_array() {
+                    pointers.push(pointer.clone());
+                }
+                node_pointers(value, &pointer, pointers);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// the kind of a token in the pretty-printed json, each kind gets its own colour
+#[derive(Clone, Copy, PartialEq)]
+pub enum Token {
+    Key,
+    String,
+    Number,
+    Boolean,
+    Null,
+    Punctuation,
+    Whitespace,
+}
+
+impl Token {
+    fn style(self, theme: &Theme) -> Style {
+        match self {
+            Token::Key => theme.key,
+            Token::String => theme.string,
+            Token::Number => theme.number,
+            Token::Boolean => theme.boolean,
+            Token::Null => theme.null,
+            Token::Punctuation => theme.punctuation,
+            Token::Whitespace => Style::default(),
+        }
+    }
+}
+
+const INDENT: &str = "    ";
+
+/// pretty-prints a json value into styled lines, token by token
 #[derive(Default)]
 pub struct MyWriter<'a> {
     pub inner: Vec<Line<'a>>,
-    pub text: Vec<String>,
-    internal_buf: Vec<u8>,
-    current_selected: Option<usize>,
-    current_idx: Option<usize>,
+    spans: Vec<Span<'a>>,
+    current_selected: Option<usize>, // index of the selected element of the root array
+    focused: String,                 // pointer of the container whose brackets are highlighted
+    in_selected: bool,
+    invalid: HashSet<String>,
+    theme: Theme,
 }
 
 impl MyWriter<'_> {
-    fn new(current_selected: Option<usize>) -> Self {
+    fn new(current_selected: Option<usize>, focused: &str, theme: &Theme) -> Self {
         Self {
             current_selected,
+            focused: focused.to_string(),
+            theme: theme.clone(),
             ..Default::default()
         }
     }
 
-    pub fn should_color(&mut self) -> bool {
-        let text_to_add = self.text.last().unwrap();
-        if text_to_add == "    {\n" {
-            self.current_idx = match self.current_idx {
-                Some(val) => Some(val + 1),
-                None => Some(0),
-            };
-        } else if text_to_add == "]\n" {
-            return false;
-        } else if self.current_idx.is_none() {
-            return false;
+    fn push(&mut self, token: Token, text: String) {
+        self.spans
+            .push(Span::styled(text, token.style(&self.theme)));
+    }
+
+    fn push_bracket(&mut self, bracket: &'static str, pointer: &str) {
+        let style = if pointer == self.focused {
+            self.theme.bracket
+        } else {
+            Token::Punctuation.style(&self.theme)
+        };
+        self.spans.push(Span::styled(bracket, style));
+    }
+
+    /// mark the last span as not matching the schema
+    fn mark_invalid(&mut self) {
+        if let Some(span) = self.spans.last_mut() {
+            span.style = span
+                .style
+                .patch(self.theme.error)
+                .add_modifier(Modifier::UNDERLINED);
+        }
+    }
+
+    fn end_line(&mut self) {
+        let line = Line::from(std::mem::take(&mut self.spans));
+        let line = if self.in_selected {
+            line.patch_style(self.theme.selected)
+        } else {
+            line
+        };
+        self.inner.push(line);
+    }
+
+    fn write_value(&mut self, value: &serde_json::Value, pointer: &str, depth: usize) {
+        use serde_json::Value;
+        let invalid = self.invalid.contains(pointer);
+        match value {
+            Value::Null => self.push(Token::Null, "null".to_string()),
+            Value::Bool(boolean) => self.push(Token::Boolean, boolean.to_string()),
+            Value::Number(number) => self.push(Token::Number, number.to_string()),
+            Value::String(string) => self.push(Token::String, quote_json_string(string)),
+            Value::Array(array) => {
+                self.push_bracket("[", pointer);
+                if invalid {
+                    self.mark_invalid();
+                }
+                if !array.is_empty() {
+                    self.end_line();
+                    for (i, value) in array.iter().enumerate() {
+                        if depth == 0 {
+                            self.in_selected = self.current_selected == Some(i);
+                        }
+                        self.push(Token::Whitespace, INDENT.repeat(depth + 1));
+                        self.write_value(value, &format!("{}/{}", pointer, i), depth + 1);
+                        if i + 1 < array.len() {
+                            self.push(Token::Punctuation, ",".to_string());
+                        }
+                        self.end_line();
+                    }
+                    if depth == 0 {
+                        self.in_selected = false;
+                    }
+                    self.push(Token::Whitespace, INDENT.repeat(depth));
+                }
+                self.push_bracket("]", pointer);
+            }
+            Value::Object(obj) => {
+                self.push_bracket("{", pointer);
+                if invalid {
+                    self.mark_invalid();
+                }
+                if !obj.is_empty() {
+                    self.end_line();
+                    for (i, (key, value)) in obj.iter().enumerate() {
+                        self.push(Token::Whitespace, INDENT.repeat(depth + 1));
+                        self.push(Token::Key, quote_json_string(key));
+                        let pointer = format!("{}/{}", pointer, escape_pointer_token(key));
+                        if self.invalid.contains(&pointer) {
+                            self.mark_invalid();
+                        }
+                        self.push(Token::Punctuation, ": ".to_string());
+                        self.write_value(value, &pointer, depth + 1);
+                        if i + 1 < obj.len() {
+                            self.push(Token::Punctuation, ",".to_string());
+                        }
+                        self.end_line();
+                    }
+                    self.push(Token::Whitespace, INDENT.repeat(depth));
+                }
+                self.push_bracket("}", pointer);
+            }
         }
-        self.current_idx == self.current_selected
-    }
-}
-
-impl io::Write for MyWriter<'_> {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.internal_buf.extend_from_slice(buf);
-        let newline = self.internal_buf.iter().position(|&r| r == b'\n');
-        if let Some(newline_idx) = newline {
-            let curr_text = String::from_utf8_lossy(&self.internal_buf[..newline_idx + 1]);
-            self.text.push(curr_text.to_string());
-            let should_color = self.should_color();
-            let text_to_add = self.text.last().unwrap();
-            let line = Line::raw(text_to_add.to_string());
-            let line = if should_color {
-                line.bg(Color::Blue)
-            } else {
-                line
-            };
-            self.inner.push(line);
-            self.internal_buf.drain(..newline_idx + 1);
+        if invalid && !value.is_array() && !value.is_object() {
+            self.mark_invalid();
         }
-        Ok(buf.len())
+    }
+}
+
+fn quote_json_string(string: &str) -> String {
+    serde_json::to_string(string).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_to_f64() {
+        let exact: serde_json::Number = "1.50e2".parse().unwrap();
+        assert_eq!(number_to_f64(&exact), (150.0, true));
+        let big: serde_json::Number = "9007199254740993".parse().unwrap();
+        assert!(!number_to_f64(&big).1);
+        let decimal: serde_json::Number = "0.1000000000000000000001".parse().unwrap();
+        assert!(!number_to_f64(&decimal).1);
+    }
+
+    #[test]
+    fn test_lines_match_pretty_printer() {
+        use serde::Serialize;
+
+        let value = serde_json::json!([{"a": [], "b": {}, "c": [1, "x\n", null]}, true]);
+        let mut container = JsonContainer::new(value.clone());
+        container.create_lines(Some(0), "/0/c", &Theme::default());
+        let text: Vec<String> = container
+            .lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+
+        let mut expected = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+        let mut ser = serde_json::Serializer::with_formatter(&mut expected, formatter);
+        value.serialize(&mut ser).unwrap();
+        assert_eq!(text.join("\n"), String::from_utf8(expected).unwrap());
     }
 
-    fn flush(&mut self) -> io::Result<()> {
-        Ok(())
+    #[test]
+    fn test_lines_keep_number_text() {
+        let value = serde_json::from_str(r#"{"id": 12345678901234567890, "rate": 1.10}"#).unwrap();
+        let mut container = JsonContainer::new(value);
+        container.create_lines(None, "", &Theme::default());
+        let text: String = container
+            .lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        assert!(text.contains("12345678901234567890"));
+        assert!(text.contains("1.10"));
     }
 }
@@ -0,0 +1,852 @@
+use std::{collections::HashMap, fmt};
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+/// everything a key can do, keys are mapped onto actions by the `KeyMap`
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    MoveTop,
+    MoveBottom,
+    EnterEdit,
+    ExitEdit,
+    Back,
+    Commit,
+    Quit,
+    QuitAndWrite,
+    QuitWithoutWriting,
+    Save,
+    Delete,
+    Yank,
+    Paste,
+    ExportCsv,
+    ImportCsv,
+    ImportCsvRaw,
+    ToggleTable,
+    SortColumn,
+    GrowColumn,
+    ShrinkColumn,
+    Help,
+    Palette,
+    SaveAs,
+    GoToPath,
+    ConvertType,
+    RenameKey,
+    MoveNodeUp,
+    MoveNodeDown,
+    SortKeys,
+    SortKeysRecursive,
+    SortArray,
+    CopyPath,
+    EditExternal,
+    SchemaErrors,
+    AddMember,
+    ExportSchema,
+    ShowChanges,
+    ExportPatch,
+    ApplyPatch,
+    MergeFile,
+    ExportMergePatch,
+    Query,
+}
+
+impl Action {
+    pub const ALL: &'static [Action] = &[
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::MoveTop,
+        Action::MoveBottom,
+        Action::EnterEdit,
+        Action::ExitEdit,
+        Action::Back,
+        Action::Commit,
+        Action::Quit,
+        Action::QuitAndWrite,
+        Action::QuitWithoutWriting,
+        Action::Save,
+        Action::Delete,
+        Action::Yank,
+        Action::Paste,
+        Action::ExportCsv,
+        Action::ImportCsv,
+        Action::ImportCsvRaw,
+        Action::ToggleTable,
+        Action::SortColumn,
+        Action::GrowColumn,
+        Action::ShrinkColumn,
+        Action::Help,
+        Action::Palette,
+        Action::SaveAs,
+        Action::GoToPath,
+        Action::ConvertType,
+        Action::RenameKey,
+        Action::MoveNodeUp,
+        Action::MoveNodeDown,
+        Action::SortKeys,
+        Action::SortKeysRecursive,
+        Action::SortArray,
+        Action::CopyPath,
+        Action::EditExternal,
+        Action::SchemaErrors,
+        Action::AddMember,
+        Action::ExportSchema,
+        Action::ShowChanges,
+        Action::ExportPatch,
+        Action::ApplyPatch,
+        Action::MergeFile,
+        Action::ExportMergePatch,
+        Action::Query,
+    ];
+
+    /// whether the action changes the document or writes it, which read-only mode forbids
+    pub fn edits(self) -> bool {
+        matches!(
+            self,
+            Action::Commit
+                | Action::QuitAndWrite
+                | Action::Save
+                | Action::SaveAs
+                | Action::Delete
+                | Action::Paste
+                | Action::ImportCsv
+                | Action::ImportCsvRaw
+                | Action::ConvertType
+                | Action::RenameKey
+                | Action::MoveNodeUp
+                | Action::MoveNodeDown
+                | Action::SortKeys
+                | Action::SortKeysRecursive
+                | Action::SortArray
+                | Action::EditExternal
+                | Action::AddMember
+                | Action::ApplyPatch
+                | Action::MergeFile
+        )
+    }
+
+    /// the name used in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveUp => "move-up",
+            Action::MoveDown => "move-down",
+            Action::MoveLeft => "move-left",
+            Action::MoveRight => "move-right",
+            Action::MoveTop => "move-top",
+            Action::MoveBottom => "move-bottom",
+            Action::EnterEdit => "enter-edit",
+            Action::ExitEdit => "exit-edit",
+            Action::Back => "back",
+            Action::Commit => "commit",
+            Action::Quit => "quit",
+            Action::QuitAndWrite => "quit-and-write",
+            Action::QuitWithoutWriting => "quit-without-writing",
+            Action::Save => "save",
+            Action::Delete => "delete",
+            Action::Yank => "yank",
+            Action::Paste => "paste",
+            Action::ExportCsv => "export-csv",
+            Action::ImportCsv => "import-csv",
+            Action::ImportCsvRaw => "import-csv-raw",
+            Action::ToggleTable => "toggle-table",
+            Action::SortColumn => "sort-column",
+            Action::GrowColumn => "grow-column",
+            Action::ShrinkColumn => "shrink-column",
+            Action::Help => "help",
+            Action::Palette => "palette",
+            Action::SaveAs => "save-as",
+            Action::GoToPath => "go-to-path",
+            Action::ConvertType => "convert-type",
+            Action::RenameKey => "rename-key",
+            Action::MoveNodeUp => "move-node-up",
+            Action::MoveNodeDown => "move-node-down",
+            Action::SortKeys => "sort-keys",
+            Action::SortKeysRecursive => "sort-keys-recursive",
+            Action::SortArray => "sort-array",
+            Action::CopyPath => "copy-path",
+            Action::EditExternal => "edit-external",
+            Action::SchemaErrors => "schema-errors",
+            Action::AddMember => "add-member",
+            Action::ExportSchema => "export-schema",
+            Action::ShowChanges => "show-changes",
+            Action::ExportPatch => "export-patch",
+            Action::ApplyPatch => "apply-patch",
+            Action::MergeFile => "merge-file",
+            Action::ExportMergePatch => "export-merge-patch",
+            Action::Query => "query",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::MoveTop => "Move to the first item",
+            Action::MoveBottom => "Move to the last item",
+            Action::EnterEdit => "Edit the selected item",
+            Action::ExitEdit => "Back to view mode",
+            Action::Back => "Back to view mode and reset the cursor",
+            Action::Commit => "Edit the selected key or value",
+            Action::Quit => "Quit",
+            Action::QuitAndWrite => "Quit and output the json",
+            Action::QuitWithoutWriting => "Quit without output",
+            Action::Save => "Save the file",
+            Action::Delete => "Delete the selected node",
+            Action::Yank => "Copy the selected node",
+            Action::Paste => "Paste after the selected node",
+            Action::ExportCsv => "Export the array to CSV/TSV",
+            Action::ImportCsv => "Import a CSV/TSV file",
+            Action::ImportCsvRaw => "Import a CSV/TSV file as strings",
+            Action::ToggleTable => "Toggle the table view",
+            Action::SortColumn => "Sort by the selected column",
+            Action::GrowColumn => "Widen the selected column",
+            Action::ShrinkColumn => "Narrow the selected column",
+            Action::Help => "Show this help",
+            Action::Palette => "Open the command palette",
+            Action::SaveAs => "Save to another file",
+            Action::GoToPath => "Go to a path",
+            Action::ConvertType => "Change the type of the selected node",
+            Action::RenameKey => "Rename the selected key",
+            Action::MoveNodeUp => "Move the selected node before its previous sibling",
+            Action::MoveNodeDown => "Move the selected node after its next sibling",
+            Action::SortKeys => "Sort the keys of the current object",
+            Action::SortKeysRecursive => "Sort the keys of the current object and of its children",
+            Action::SortArray => "Sort the current array by value or by a field",
+            Action::CopyPath => "Copy the path of the selected node",
+            Action::EditExternal => "Edit the selected node in $VISUAL or $EDITOR",
+            Action::SchemaErrors => "Show the schema errors",
+            Action::AddMember => "Add a node after the selected one",
+            Action::ExportSchema => "Export a JSON Schema inferred from the document",
+            Action::ShowChanges => "Review the changes since the file was loaded",
+            Action::ExportPatch => "Export the edits of the session as a JSON Patch",
+            Action::ApplyPatch => "Apply a JSON Patch file, with a preview",
+            Action::MergeFile => "Merge a JSON file with Merge Patch semantics, with a preview",
+            Action::ExportMergePatch => {
+                "Export the merge patch from the loaded to the edited document"
+            }
+            Action::Query => "Query the selected node or the document with a jq filter",
+        }
+    }
+
+    /// whether the action is offered by the command palette
+    pub fn in_palette(self) -> bool {
+        Context::Main.actions().contains(&self)
+            && !matches!(
+                self,
+                Action::MoveUp
+                    | Action::MoveDown
+                    | Action::MoveTop
+                    | Action::MoveBottom
+                    | Action::Palette
+            )
+    }
+
+    /// whether a count prefix repeats the action
+    fn repeatable(self) -> bool {
+        matches!(
+            self,
+            Action::MoveUp
+                | Action::MoveDown
+                | Action::MoveLeft
+                | Action::MoveRight
+                | Action::Delete
+                | Action::Paste
+                | Action::GrowColumn
+                | Action::ShrinkColumn
+        )
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|action| action.name() == name)
+    }
+}
+
+/// the set of bindings in use depends on the screen
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Context {
+    Main,
+    Editing,
+    Exiting,
+    Table,
+}
+
+impl Context {
+    pub fn name(self) -> &'static str {
+        match self {
+            Context::Main => "View Mode",
+            Context::Editing => "Editing Mode",
+            Context::Exiting => "Exiting",
+            Context::Table => "Table Mode",
+        }
+    }
+
+    /// the actions that can be run in this context
+    pub fn actions(self) -> &'static [Action] {
+        match self {
+            Context::Main => &[
+                Action::MoveUp,
+                Action::MoveDown,
+                Action::MoveTop,
+                Action::MoveBottom,
+                Action::EnterEdit,
+                Action::GoToPath,
+                Action::Save,
+                Action::SaveAs,
+                Action::Delete,
+                Action::Yank,
+                Action::Paste,
+                Action::ExportCsv,
+                Action::ImportCsv,
+                Action::ImportCsvRaw,
+                Action::ToggleTable,
+                Action::Palette,
+                Action::ConvertType,
+                Action::RenameKey,
+                Action::MoveNodeUp,
+                Action::MoveNodeDown,
+                Action::SortKeys,
+                Action::SortKeysRecursive,
+                Action::SortArray,
+                Action::CopyPath,
+                Action::EditExternal,
+                Action::SchemaErrors,
+                Action::AddMember,
+                Action::ExportSchema,
+                Action::ShowChanges,
+                Action::ExportPatch,
+                Action::ApplyPatch,
+                Action::MergeFile,
+                Action::ExportMergePatch,
+                Action::Query,
+                Action::Help,
+                Action::Quit,
+            ],
+            Context::Editing => &[
+                Action::MoveUp,
+                Action::MoveDown,
+                Action::MoveTop,
+                Action::MoveBottom,
+                Action::Commit,
+                Action::ExitEdit,
+                Action::Back,
+                Action::ConvertType,
+                Action::RenameKey,
+                Action::MoveNodeUp,
+                Action::MoveNodeDown,
+                Action::Yank,
+                Action::Paste,
+                Action::CopyPath,
+                Action::EditExternal,
+                Action::AddMember,
+                Action::ShowChanges,
+                Action::Help,
+            ],
+            Context::Exiting => &[
+                Action::QuitAndWrite,
+                Action::QuitWithoutWriting,
+                Action::Help,
+            ],
+            Context::Table => &[
+                Action::MoveUp,
+                Action::MoveDown,
+                Action::MoveLeft,
+                Action::MoveRight,
+                Action::MoveTop,
+                Action::MoveBottom,
+                Action::EnterEdit,
+                Action::SortColumn,
+                Action::GrowColumn,
+                Action::ShrinkColumn,
+                Action::ToggleTable,
+                Action::Help,
+            ],
+        }
+    }
+}
+
+/// a key with its modifiers, shift is folded into the character
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct KeyPress {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl From<KeyEvent> for KeyPress {
+    fn from(key: KeyEvent) -> Self {
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        let (code, modifiers) = match key.code {
+            // shift is already part of the character
+            KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => (
+                KeyCode::Char(c.to_ascii_lowercase()),
+                key.modifiers - KeyModifiers::SHIFT,
+            ),
+            KeyCode::Char(_) | KeyCode::BackTab => (key.code, key.modifiers - KeyModifiers::SHIFT),
+            KeyCode::Tab if shift => (KeyCode::BackTab, key.modifiers - KeyModifiers::SHIFT),
+            code => (code, key.modifiers),
+        };
+        Self {
+            code,
+            modifiers: modifiers
+                & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT),
+        }
+    }
+}
+
+impl KeyPress {
+    /// parse keys like `j`, `G`, `ctrl-s`, `alt-<`, `shift-up`, `f1`, `space`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text;
+        loop {
+            let lower = rest.to_lowercase();
+            if lower.starts_with("ctrl-") && rest.len() > 5 {
+                modifiers |= KeyModifiers::CONTROL;
+                rest = &rest[5..];
+            } else if lower.starts_with("alt-") && rest.len() > 4 {
+                modifiers |= KeyModifiers::ALT;
+                rest = &rest[4..];
+            } else if lower.starts_with("shift-") && rest.len() > 6 {
+                modifiers |= KeyModifiers::SHIFT;
+                rest = &rest[6..];
+            } else {
+                break;
+            }
+        }
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "space" => KeyCode::Char(' '),
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key \"{}\"", text)),
+                },
+            },
+        };
+        Ok(Self::from(KeyEvent::new(code, modifiers)))
+    }
+
+    /// parse a space separated sequence of keys, like `g g`
+    pub fn parse_sequence(text: &str) -> Result<Vec<Self>, String> {
+        let keys = text
+            .split_whitespace()
+            .map(Self::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        if keys.is_empty() {
+            return Err("empty key sequence".to_string());
+        }
+        Ok(keys)
+    }
+}
+
+impl fmt::Display for KeyPress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            KeyCode::BackTab => write!(f, "shift-tab"),
+            code => write!(f, "{}", code.to_string().to_lowercase()),
+        }
+    }
+}
+
+/// format a key sequence the way it is written in the config file
+pub fn sequence_to_string(keys: &[KeyPress]) -> String {
+    keys.iter()
+        .map(KeyPress::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// the `[keys]` section of the config file
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeysConfig {
+    pub preset: Option<String>,
+    pub main: HashMap<String, String>,
+    pub editing: HashMap<String, String>,
+    pub exiting: HashMap<String, String>,
+    pub table: HashMap<String, String>,
+}
+
+/// what a key press resolved to
+pub enum Resolved {
+    Action(Action, usize), // the action and how many times to run it
+    Pending,               // the key starts a sequence or a count
+    Unbound,
+}
+
+type Bindings = Vec<(Vec<KeyPress>, Action)>;
+
+#[derive(Default)]
+pub struct KeyMap {
+    bindings: HashMap<Context, Bindings>,
+    pending: Vec<KeyPress>,
+    count: Option<usize>,
+}
+
+const DEFAULT_PRESET: &[(Context, &str, Action)] = &[
+    (Context::Main, "up", Action::MoveUp),
+    (Context::Main, "down", Action::MoveDown),
+    (Context::Main, "home", Action::MoveTop),
+    (Context::Main, "end", Action::MoveBottom),
+    (Context::Main, "right", Action::EnterEdit),
+    (Context::Main, "tab", Action::EnterEdit),
+    (Context::Main, "q", Action::Quit),
+    (Context::Main, "ctrl-s", Action::Save),
+    (Context::Main, "delete", Action::Delete),
+    (Context::Main, "c", Action::Yank),
+    (Context::Main, "v", Action::Paste),
+    (Context::Main, "x", Action::ExportCsv),
+    (Context::Main, "i", Action::ImportCsv),
+    (Context::Main, "I", Action::ImportCsvRaw),
+    (Context::Main, "t", Action::ToggleTable),
+    (Context::Main, "?", Action::Help),
+    (Context::Main, "f1", Action::Help),
+    (Context::Main, "ctrl-p", Action::Palette),
+    (Context::Main, "/", Action::GoToPath),
+    (Context::Editing, "up", Action::MoveUp),
+    (Context::Editing, "down", Action::MoveDown),
+    (Context::Editing, "left", Action::Back),
+    (Context::Editing, "tab", Action::ExitEdit),
+    (Context::Editing, "esc", Action::ExitEdit),
+    (Context::Editing, "enter", Action::Commit),
+    (Context::Editing, "f1", Action::Help),
+    (Context::Editing, "c", Action::Yank),
+    (Context::Editing, "v", Action::Paste),
+    (Context::Exiting, "y", Action::QuitAndWrite),
+    (Context::Exiting, "n", Action::QuitWithoutWriting),
+    (Context::Exiting, "q", Action::QuitWithoutWriting),
+    (Context::Exiting, "?", Action::Help),
+    (Context::Exiting, "f1", Action::Help),
+    (Context::Table, "up", Action::MoveUp),
+    (Context::Table, "down", Action::MoveDown),
+    (Context::Table, "left", Action::MoveLeft),
+    (Context::Table, "right", Action::MoveRight),
+    (Context::Table, "home", Action::MoveTop),
+    (Context::Table, "end", Action::MoveBottom),
+    (Context::Table, "enter", Action::EnterEdit),
+    (Context::Table, "s", Action::SortColumn),
+    (Context::Table, "+", Action::GrowColumn),
+    (Context::Table, "-", Action::ShrinkColumn),
+    (Context::Table, "t", Action::ToggleTable),
+    (Context::Table, "q", Action::ToggleTable),
+    (Context::Table, "esc", Action::ToggleTable),
+    (Context::Table, "?", Action::Help),
+    (Context::Table, "f1", Action::Help),
+    (Context::Main, "T", Action::ConvertType),
+    (Context::Editing, "ctrl-t", Action::ConvertType),
+    (Context::Main, "r", Action::RenameKey),
+    (Context::Editing, "ctrl-r", Action::RenameKey),
+    (Context::Main, "alt-up", Action::MoveNodeUp),
+    (Context::Editing, "alt-up", Action::MoveNodeUp),
+    (Context::Main, "alt-down", Action::MoveNodeDown),
+    (Context::Editing, "alt-down", Action::MoveNodeDown),
+    (Context::Main, "s", Action::SortKeys),
+    (Context::Main, "S", Action::SortKeysRecursive),
+    (Context::Main, "o", Action::SortArray),
+    (Context::Main, "C", Action::CopyPath),
+    (Context::Editing, "C", Action::CopyPath),
+    (Context::Main, "e", Action::EditExternal),
+    (Context::Editing, "e", Action::EditExternal),
+    (Context::Main, "!", Action::SchemaErrors),
+    (Context::Main, "a", Action::AddMember),
+    (Context::Editing, "a", Action::AddMember),
+    (Context::Main, "X", Action::ExportSchema),
+    (Context::Main, "D", Action::ShowChanges),
+    (Context::Editing, "D", Action::ShowChanges),
+    (Context::Main, "W", Action::ExportPatch),
+    (Context::Main, "P", Action::ApplyPatch),
+    (Context::Main, "M", Action::MergeFile),
+    (Context::Main, "alt-m", Action::ExportMergePatch),
+    (Context::Main, "|", Action::Query),
+];
+
+/// vim bindings, on top of the default ones
+const VIM_PRESET: &[(Context, &str, Action)] = &[
+    (Context::Main, "k", Action::MoveUp),
+    (Context::Main, "j", Action::MoveDown),
+    (Context::Main, "g g", Action::MoveTop),
+    (Context::Main, "G", Action::MoveBottom),
+    (Context::Main, "l", Action::EnterEdit),
+    (Context::Main, "i", Action::EnterEdit),
+    (Context::Main, "d d", Action::Delete),
+    (Context::Main, "y y", Action::Yank),
+    (Context::Main, "p", Action::Paste),
+    (Context::Editing, "y y", Action::Yank),
+    (Context::Editing, "p", Action::Paste),
+    (Context::Main, "Z Z", Action::QuitAndWrite),
+    (Context::Main, "Z Q", Action::QuitWithoutWriting),
+    (Context::Main, ":", Action::Palette),
+    (Context::Editing, "ctrl-k", Action::MoveUp),
+    (Context::Editing, "ctrl-j", Action::MoveDown),
+    (Context::Editing, "ctrl-c", Action::ExitEdit),
+    (Context::Table, "k", Action::MoveUp),
+    (Context::Table, "j", Action::MoveDown),
+    (Context::Table, "h", Action::MoveLeft),
+    (Context::Table, "l", Action::MoveRight),
+    (Context::Table, "g g", Action::MoveTop),
+    (Context::Table, "G", Action::MoveBottom),
+    (Context::Table, "i", Action::EnterEdit),
+];
+
+/// emacs bindings, on top of the default ones
+const EMACS_PRESET: &[(Context, &str, Action)] = &[
+    (Context::Main, "ctrl-p", Action::MoveUp),
+    (Context::Main, "ctrl-n", Action::MoveDown),
+    (Context::Main, "alt-<", Action::MoveTop),
+    (Context::Main, "alt->", Action::MoveBottom),
+    (Context::Main, "ctrl-f", Action::EnterEdit),
+    (Context::Main, "ctrl-k", Action::Delete),
+    (Context::Main, "alt-w", Action::Yank),
+    (Context::Main, "ctrl-y", Action::Paste),
+    (Context::Editing, "alt-w", Action::Yank),
+    (Context::Editing, "ctrl-y", Action::Paste),
+    (Context::Main, "ctrl-x ctrl-s", Action::Save),
+    (Context::Main, "ctrl-x ctrl-c", Action::Quit),
+    (Context::Main, "ctrl-x ctrl-w", Action::SaveAs),
+    (Context::Main, "alt-x", Action::Palette),
+    (Context::Editing, "ctrl-p", Action::MoveUp),
+    (Context::Editing, "ctrl-n", Action::MoveDown),
+    (Context::Editing, "ctrl-g", Action::ExitEdit),
+    (Context::Table, "ctrl-p", Action::MoveUp),
+    (Context::Table, "ctrl-n", Action::MoveDown),
+    (Context::Table, "ctrl-b", Action::MoveLeft),
+    (Context::Table, "ctrl-f", Action::MoveRight),
+    (Context::Table, "ctrl-g", Action::ToggleTable),
+    (Context::Main, "alt-!", Action::SchemaErrors),
+    (Context::Main, "ctrl-o", Action::AddMember),
+    (Context::Editing, "ctrl-o", Action::AddMember),
+    (Context::Main, "ctrl-x s", Action::ExportSchema),
+    (Context::Main, "ctrl-x d", Action::ShowChanges),
+    (Context::Editing, "ctrl-x d", Action::ShowChanges),
+];
+
+impl KeyMap {
+    pub const PRESETS: [&'static str; 3] = ["default", "vim", "emacs"];
+
+    /// build the bindings from a preset and the overrides of the config file,
+    /// an override bound to `none` removes the binding
+    pub fn new(config: &KeysConfig) -> Result<Self, String> {
+        let preset = match config.preset.as_deref().unwrap_or("default") {
+            "default" => &[][..],
+            "vim" => VIM_PRESET,
+            "emacs" => EMACS_PRESET,
+            other => {
+                return Err(format!(
+                    "unknown key preset \"{}\", the presets are {}",
+                    other,
+                    Self::PRESETS.join(", ")
+                ))
+            }
+        };
+        let mut keymap = Self::default();
+        for (context, keys, action) in DEFAULT_PRESET.iter().chain(preset) {
+            let keys = KeyPress::parse_sequence(keys)?;
+            keymap.bind(*context, keys, Some(*action));
+        }
+        for (context, overrides) in [
+            (Context::Main, &config.main),
+            (Context::Editing, &config.editing),
+            (Context::Exiting, &config.exiting),
+            (Context::Table, &config.table),
+        ] {
+            for (keys, name) in overrides {
+                let keys = KeyPress::parse_sequence(keys)?;
+                let action = match name.as_str() {
+                    "none" => None,
+                    name => Some(
+                        Action::from_name(name)
+                            .ok_or_else(|| format!("unknown action \"{}\"", name))?,
+                    ),
+                };
+                keymap.bind(context, keys, action);
+            }
+        }
+        Ok(keymap)
+    }
+
+    fn bind(&mut self, context: Context, keys: Vec<KeyPress>, action: Option<Action>) {
+        let bindings = self.bindings.entry(context).or_default();
+        bindings.retain(|(bound, _)| *bound != keys);
+        if let Some(action) = action {
+            bindings.push((keys, action));
+        }
+    }
+
+    /// the bindings of a context, in the order they were defined
+    pub fn bindings(&self, context: Context) -> &[(Vec<KeyPress>, Action)] {
+        self.bindings.get(&context).map_or(&[], Vec::as_slice)
+    }
+
+    /// the first keys bound to `action`, formatted for display
+    pub fn key_for(&self, context: Context, action: Action) -> Option<String> {
+        self.keys_for(context, action).into_iter().next()
+    }
+
+    /// all the keys bound to `action`, formatted for display
+    pub fn keys_for(&self, context: Context, action: Action) -> Vec<String> {
+        self.bindings(context)
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(keys, _)| sequence_to_string(keys))
+            .collect()
+    }
+
+    /// the keys pressed so far in a pending sequence or count, to show to the user
+    pub fn pending(&self) -> String {
+        let count = self
+            .count
+            .map(|count| count.to_string())
+            .unwrap_or_default();
+        format!("{}{}", count, sequence_to_string(&self.pending))
+    }
+
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.count = None;
+    }
+
+    /// feed a key press, digits before an action are a count prefix (in the
+    /// contexts without text input), and keys can be chained into sequences
+    pub fn resolve(&mut self, context: Context, key: KeyEvent) -> Resolved {
+        let key = KeyPress::from(key);
+        let bindings = self.bindings.get(&context).map_or(&[][..], Vec::as_slice);
+        let counts = matches!(context, Context::Main | Context::Table);
+        if let (true, true, KeyCode::Char(c @ '0'..='9')) =
+            (counts, self.pending.is_empty(), key.code)
+        {
+            let bound = bindings.iter().any(|(keys, _)| keys == &[key]);
+            if key.modifiers.is_empty() && (self.count.is_some() || (c != '0' && !bound)) {
+                let digit = c.to_digit(10).unwrap_or_default() as usize;
+                self.count = Some(
+                    self.count
+                        .unwrap_or_default()
+                        .saturating_mul(10)
+                        .saturating_add(digit),
+                );
+                return Resolved::Pending;
+            }
+        }
+
+        self.pending.push(key);
+        let found = bindings
+            .iter()
+            .find(|(keys, _)| *keys == self.pending)
+            .map(|(_, action)| *action);
+        let is_prefix = bindings
+            .iter()
+            .any(|(keys, _)| keys.starts_with(&self.pending));
+        if let Some(action) = found {
+            let count = match action.repeatable() {
+                true => self.count.unwrap_or(1),
+                false => 1,
+            };
+            self.reset();
+            return Resolved::Action(action, count);
+        }
+        if is_prefix {
+            return Resolved::Pending;
+        }
+        // a key that breaks a sequence is tried on its own
+        let was_pending = self.pending.len() > 1;
+        self.reset();
+        if was_pending {
+            return self.resolve(context, KeyEvent::new(key.code, key.modifiers));
+        }
+        Resolved::Unbound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(keymap: &mut KeyMap, context: Context, keys: &str) -> Option<(Action, usize)> {
+        let mut last = None;
+        for key in KeyPress::parse_sequence(keys).unwrap() {
+            last = match keymap.resolve(context, KeyEvent::new(key.code, key.modifiers)) {
+                Resolved::Action(action, count) => Some((action, count)),
+                _ => None,
+            };
+        }
+        last
+    }
+
+    #[test]
+    fn test_vim_sequences_and_counts() {
+        let config = KeysConfig {
+            preset: Some("vim".to_string()),
+            ..Default::default()
+        };
+        let mut keymap = KeyMap::new(&config).unwrap();
+        assert_eq!(
+            press(&mut keymap, Context::Main, "g g"),
+            Some((Action::MoveTop, 1))
+        );
+        assert_eq!(
+            press(&mut keymap, Context::Main, "1 2 j"),
+            Some((Action::MoveDown, 12))
+        );
+        assert_eq!(
+            press(&mut keymap, Context::Main, "3 d d"),
+            Some((Action::Delete, 3))
+        );
+        assert_eq!(
+            press(&mut keymap, Context::Main, "d j"),
+            Some((Action::MoveDown, 1))
+        );
+    }
+
+    #[test]
+    fn test_overrides() {
+        let mut config = KeysConfig::default();
+        config.main.insert("ctrl-alt-x".into(), "save".into());
+        config.main.insert("q".into(), "none".into());
+        let mut keymap = KeyMap::new(&config).unwrap();
+        assert_eq!(
+            press(&mut keymap, Context::Main, "ctrl-alt-x"),
+            Some((Action::Save, 1))
+        );
+        assert_eq!(press(&mut keymap, Context::Main, "q"), None);
+        config.main.insert("q".into(), "fly".into());
+        assert!(KeyMap::new(&config).is_err());
+    }
+}
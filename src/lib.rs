@@ -1,46 +1,193 @@
 mod app;
+mod clipboard;
+mod command;
+mod compare;
+mod config;
+mod convert;
+mod csv;
+mod diff;
+mod edit;
+mod external;
+mod help;
+mod infer;
 mod json;
+mod keymap;
 mod logic;
+mod merge;
+mod palette;
+mod patch;
+mod query;
+mod schema;
+mod table;
+mod text_input;
+mod theme;
 mod ui;
 
 use std::io;
 
 use app::App;
+use command::{Cli, Command};
 use ratatui::{
     backend::CrosstermBackend,
     crossterm::{
-        event::{DisableMouseCapture, EnableMouseCapture},
+        event::{
+            DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        },
         execute,
         terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     },
     Terminal,
 };
 
+/// put the terminal in the mode the interface runs in
+fn enter_tui(mouse: bool) -> io::Result<()> {
+    enable_raw_mode()?;
+    execute!(io::stderr(), EnterAlternateScreen, EnableBracketedPaste)?;
+    if mouse {
+        execute!(io::stderr(), EnableMouseCapture)?;
+    }
+    Ok(())
+}
+
+/// give the terminal back, to quit or to run another program
+fn leave_tui() -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        io::stderr(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste
+    )
+}
+
+/// the command line options besides the edited file
+#[derive(Default)]
+pub struct Options {
+    pub schema: Option<String>, // validate against this schema instead of the discovered one
+    pub diff: Option<(String, String)>, // compare two files instead of editing one
+    pub readonly: bool,
+    pub indent: Option<usize>,  // spaces to indent the written JSON with
+    pub compact: bool,          // write compact JSON, whatever the file was
+    pub output: Option<String>, // where to write instead of the edited file
+    pub no_mouse: bool,
+    pub theme: Option<String>,
+    pub path: Option<String>, // the node to open focused on
+}
+
+/// what the command line asks for
+pub enum Start {
+    /// open the interface on the file with the options
+    Run {
+        input_file: Option<String>,
+        options: Options,
+    },
+    /// exit with this code, once a subcommand ran or the help was printed
+    Exit(i32),
+}
+
+/// read the command line: the file to edit and the options of the interface, or the exit
+/// code once a subcommand ran or the help was printed
+pub fn cli_args(args: &[String]) -> Start {
+    if let Some(code) = cli_command(args) {
+        return Start::Exit(code);
+    }
+    match command::parse_options(args) {
+        Ok(Cli::Edit {
+            input_file,
+            options,
+        }) => Start::Run {
+            input_file,
+            options,
+        },
+        Ok(Cli::Help) => {
+            println!("{}\n\n{}", command::USAGE, command::HELP);
+            Start::Exit(0)
+        }
+        Ok(Cli::Version) => {
+            println!("json-editor {}", env!("CARGO_PKG_VERSION"));
+            Start::Exit(0)
+        }
+        Err(e) => {
+            eprintln!("json-editor: {}\nRun json-editor --help for the usage", e);
+            Start::Exit(2)
+        }
+    }
+}
+
+/// run a subcommand without the interface, returns its exit code, or `None` when the
+/// arguments open the interface
+fn cli_command(args: &[String]) -> Option<i32> {
+    let command = match Command::parse(args)? {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("json-editor: {}\n{}", e, command::USAGE);
+            return Some(2);
+        }
+    };
+    Some(match command.run() {
+        Ok(output) => {
+            if !output.is_empty() {
+                println!("{}", output);
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("json-editor: {}", e);
+            1
+        }
+    })
+}
+
 pub fn cli_main(input_file: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    cli_main_with(input_file, Options::default())
+}
+
+pub fn cli_main_with(
+    input_file: Option<String>,
+    options: Options,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // create app, before the terminal setup to report errors on a normal screen
+    let mut app = match (options.diff, input_file) {
+        (Some((left, right)), _) => App::compare_files(&left, &right)?,
+        // a missing file is created, but one that cannot be read or parsed is an error
+        (None, Some(file)) if std::path::Path::new(&file).exists() => App::open(&file)?,
+        (None, input_file) => App::new(input_file),
+    };
+    app.readonly = options.readonly;
+    if options.compact {
+        (app.indent, app.newline) = (None, false);
+    } else if let Some(indent) = options.indent {
+        (app.indent, app.newline) = (Some(" ".repeat(indent)), true);
+    }
+    app.output = options.output;
+    app.no_mouse = options.no_mouse;
+    if let Some(theme) = options.theme {
+        app.use_theme(&theme)?;
+    }
+    if let Some(schema) = options.schema {
+        app.use_schema(std::path::Path::new(&schema));
+    }
+    if let Some(path) = options.path {
+        app.go_to_path(&path);
+    }
+
     // setup terminal
-    enable_raw_mode()?;
-    let mut stderr = io::stderr(); // This is a special case. Normally using stdout is fine
-    execute!(stderr, EnterAlternateScreen, EnableMouseCapture)?;
+    enter_tui(!app.no_mouse)?;
+    let stderr = io::stderr(); // This is a special case. Normally using stdout is fine
 
     let backend = CrosstermBackend::new(stderr);
     let mut terminal = Terminal::new(backend)?;
 
-    // create app and run it
-    let mut app = App::new(input_file);
+    // run it
     let res = app.run_app(&mut terminal);
 
     // restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    leave_tui()?;
     terminal.show_cursor()?;
 
     if let Ok(do_print) = res {
         if do_print {
-            app.write_output();
+            app.write_output()?;
         }
     } else if let Err(err) = res {
         println!("{err:?}");
@@ -2,11 +2,18 @@ use std::io;
 
 use ratatui::{
     backend::Backend,
-    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
     Terminal,
 };
+use serde_json::Value;
 
-use crate::app::{App, CurrentScreen, CurrentlyEditing};
+use crate::{
+    app::{App, CurrentScreen, CurrentlyEditing, Prompt, PromptKind, ValueKind},
+    edit::rename_key,
+    keymap::{Action, Context, Resolved},
+    patch::Operation,
+    text_input::TextInput,
+};
 
 impl App<'_> {
     pub fn run_app<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> io::Result<bool> {
@@ -16,12 +23,59 @@ impl App<'_> {
             if let Some(res) = self.handle_event() {
                 return Ok(res);
             }
+            // running an editor needs the terminal, which actions do not have
+            if let Some(pointer) = self.external_edit.take() {
+                self.edit_externally(terminal, &pointer)?;
+            }
         }
     }
 
-    fn reset_cursor(&mut self) {
-        self.json_container.input_cursor = None;
-        self.json_container.max_cursor = None;
+    pub fn open_prompt(&mut self, kind: PromptKind, input: String) {
+        let previous = std::mem::replace(&mut self.current_screen, CurrentScreen::Prompt);
+        self.prompt = Some(Prompt::new(kind, input, previous));
+    }
+
+    fn run_prompt(&mut self, prompt: Prompt) {
+        match prompt.kind {
+            PromptKind::ExportCsv => self.export_csv(&prompt.input),
+            PromptKind::ImportCsv { infer_types } => self.import_csv(&prompt.input, infer_types),
+            PromptKind::SaveAs => self.save_as(&prompt.input),
+            PromptKind::GoToPath => self.go_to_path(&prompt.input),
+            PromptKind::RenameKey => self.rename_selected(&prompt.input),
+            PromptKind::SortArray => self.sort_selected_array(&prompt.input),
+            PromptKind::ExportSchema => self.export_schema(&prompt.input),
+            PromptKind::IgnoreKeys => self.set_ignored_keys(&prompt.input),
+            PromptKind::ExportPatch => self.export_patch(&prompt.input),
+            PromptKind::ApplyPatch => self.open_patch(&prompt.input),
+            PromptKind::MergeFile => self.open_merge(&prompt.input),
+            PromptKind::ExportMergePatch => self.export_merge_patch(&prompt.input),
+            PromptKind::Query => self.run_query(&prompt.input),
+        }
+    }
+
+    /// the key bindings in use on the current screen, `None` for screens taking raw text
+    pub fn key_context(&self) -> Option<Context> {
+        if self.help.is_some()
+            || self.palette.is_some()
+            || self.convert.is_some()
+            || self.error_panel.is_some()
+            || self.diff.is_some()
+            || self.patch_preview.is_some()
+            || self.query.is_some()
+        {
+            return None;
+        }
+        match self.current_screen {
+            CurrentScreen::Main => Some(Context::Main),
+            CurrentScreen::Editing if self.currently_editing.is_some() => None,
+            CurrentScreen::Editing => Some(Context::Editing),
+            CurrentScreen::Exiting => Some(Context::Exiting),
+            CurrentScreen::Table => match &self.table {
+                Some(table) if table.cell_input.is_some() => None,
+                _ => Some(Context::Table),
+            },
+            CurrentScreen::Prompt => None,
+        }
     }
 
     pub fn handle_event(&mut self) -> Option<bool> {
@@ -32,161 +86,457 @@ impl App<'_> {
                 return None;
             }
         };
+        if let Event::Paste(text) = &event {
+            self.status = None;
+            self.paste_text(text);
+        }
         if let Event::Key(key) = event {
             if key.kind == event::KeyEventKind::Release {
                 // Skip events that are not KeyEventKind::Press
                 return None;
             }
-            match self.current_screen {
-                CurrentScreen::Main => match key.code {
-                    KeyCode::Right => {
-                        self.current_screen = CurrentScreen::Editing;
-                        self.currently_editing = Some(CurrentlyEditing::Key);
+            self.status = None;
+            if self.help.is_some() {
+                self.handle_help_key(key);
+                return None;
+            }
+            if self.palette.is_some() {
+                return self.handle_palette_key(key);
+            }
+            if self.compare.is_some() && self.prompt.is_none() {
+                return self.handle_compare_key(key);
+            }
+            if self.query.is_some() && self.prompt.is_none() {
+                self.handle_query_key(key);
+                return None;
+            }
+            if self.patch_preview.is_some() {
+                self.handle_patch_preview_key(key);
+                return None;
+            }
+            if self.diff.is_some() {
+                self.handle_diff_key(key);
+                return None;
+            }
+            if self.error_panel.is_some() {
+                self.handle_error_panel_key(key);
+                return None;
+            }
+            if self.convert.is_some() {
+                self.handle_convert_key(key);
+                return None;
+            }
+            let Some(context) = self.key_context() else {
+                self.handle_text_key(key);
+                return None;
+            };
+            match self.keymap.resolve(context, key) {
+                Resolved::Action(action, count) => {
+                    for _ in 0..count {
+                        if let Some(res) = self.run_action(action) {
+                            return Some(res);
+                        }
                     }
-                    KeyCode::Tab => {
-                        self.current_screen = CurrentScreen::Editing;
-                        self.currently_editing = Some(CurrentlyEditing::Key);
+                }
+                Resolved::Pending => self.status = Some(self.keymap.pending()),
+                Resolved::Unbound => {}
+            }
+        }
+        None
+    }
+
+    /// open the edit popup on the selected node, with its key and value
+    pub fn start_editing(&mut self) {
+        let (text, kind) = match self.json_container.inner.pointer(&self.selected_pointer()) {
+            Some(Value::String(string)) => (string.clone(), ValueKind::String),
+            Some(value) => (value.to_string(), ValueKind::of(value)),
+            None => (String::new(), ValueKind::String),
+        };
+        self.json_container.input_buffer = TextInput::new(&text);
+        self.key_suggestion = None;
+        self.value_choice = None;
+        self.new_member = false;
+        self.currently_editing = match self.selected_key() {
+            Some(key) => {
+                self.json_container.key_buffer = TextInput::new(&key);
+                Some(CurrentlyEditing::Key)
+            }
+            None => Some(CurrentlyEditing::Value(kind)),
+        };
+    }
+
+    /// the type the edited value must keep, the one of the selected node
+    pub fn edited_kind(&self) -> ValueKind {
+        match (
+            &self.currently_editing,
+            self.json_container.inner.pointer(&self.selected_pointer()),
+        ) {
+            (Some(CurrentlyEditing::Value(kind)), _) => *kind,
+            (_, Some(value)) => ValueKind::of(value),
+            (_, None) => ValueKind::String,
+        }
+    }
+
+    /// the typed value, checked against the type of the edited value
+    pub fn edited_value(&self) -> Option<Result<Value, String>> {
+        self.currently_editing.as_ref()?;
+        Some(
+            self.json_container
+                .input_buffer
+                .value()
+                .and_then(|text| self.edited_kind().parse(&text)),
+        )
+    }
+
+    /// rename the selected node to the edited key and replace it with the edited value
+    /// (or `value`), if they are valid; the popup stays open otherwise
+    pub fn save_edit(&mut self, value: Option<Value>) {
+        let value = match value.map(Ok).or_else(|| self.edited_value()) {
+            Some(Ok(value)) => value,
+            Some(Err(e)) => {
+                self.status = Some(format!("Invalid value: {}", e));
+                self.currently_editing = Some(CurrentlyEditing::Value(self.edited_kind()));
+                return;
+            }
+            None => return,
+        };
+        if let Some(old_key) = self.selected_key() {
+            let key = match self.json_container.key_buffer.value() {
+                Ok(key) => key,
+                Err(e) => {
+                    self.status = Some(e);
+                    self.currently_editing = Some(CurrentlyEditing::Key);
+                    return;
+                }
+            };
+            if old_key != key {
+                let pointer = self.selected_pointer();
+                match rename_key(&mut self.json_container.inner, &pointer, &key) {
+                    Ok(renamed) => {
+                        self.record(Operation::Move {
+                            from: pointer,
+                            path: renamed.clone(),
+                        });
+                        self.select_pointer(&renamed);
                     }
-                    KeyCode::Up => match self.index_edition {
-                        Some(val) => {
-                            if val == 0 {
-                                return None;
-                            } else {
-                                self.index_edition = Some(val.saturating_sub(1));
-                            }
-                        }
-                        None => {
-                            self.index_edition = Some(0);
-                        }
-                    },
-                    KeyCode::Down => match self.index_edition {
-                        Some(val) => match self.json_container.len() {
-                            Some(len) => {
-                                if val == (len - 1) {
-                                    return None;
-                                }
-                                self.index_edition = Some(val.saturating_add(1));
-                            }
-                            None => {
-                                return None;
-                            }
-                        },
-                        None => match self.json_container.len() {
-                            Some(_len) => {
-                                self.index_edition = Some(0);
-                            }
-                            None => {
-                                return None;
-                            }
-                        },
-                    },
-                    KeyCode::Char('q') => {
-                        self.current_screen = CurrentScreen::Exiting;
+                    Err(e) => {
+                        self.status = Some(format!("Cannot rename: {}", e));
+                        self.currently_editing = Some(CurrentlyEditing::Key);
+                        return;
                     }
-                    _ => {}
-                },
+                }
+            }
+        }
+        let pointer = self.selected_pointer();
+        self.replace_at(&pointer, value);
+        self.select_pointer(&pointer);
+        self.currently_editing = None;
+    }
 
-                CurrentScreen::Exiting => match key.code {
-                    KeyCode::Char('y') => {
-                        return Some(true);
+    /// the input of the edited key or value
+    pub fn focused_buffer(&mut self) -> &mut TextInput {
+        match self.currently_editing {
+            Some(CurrentlyEditing::Key) => &mut self.json_container.key_buffer,
+            _ => &mut self.json_container.input_buffer,
+        }
+    }
+
+    fn toggle_edited_boolean(&mut self) {
+        let toggled = match self.edited_value() {
+            Some(Ok(Value::Bool(true))) => "false",
+            _ => "true",
+        };
+        self.json_container.input_buffer = TextInput::new(toggled);
+    }
+
+    /// keys typed while editing a key or a value
+    fn handle_edit_key(&mut self, key: KeyEvent) {
+        if self.handle_suggestion_key(key) {
+            return;
+        }
+        let Some(editing) = &self.currently_editing else {
+            return;
+        };
+        let kind = match editing {
+            CurrentlyEditing::Key => None,
+            CurrentlyEditing::Value(kind) => Some(*kind),
+        };
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Enter if !key.modifiers.contains(KeyModifiers::ALT) => match kind {
+                None => self.currently_editing = Some(CurrentlyEditing::Value(self.edited_kind())),
+                Some(_) => self.save_edit(None),
+            },
+            KeyCode::Tab | KeyCode::BackTab => match kind {
+                None => self.currently_editing = Some(CurrentlyEditing::Value(self.edited_kind())),
+                Some(_) if self.selected_key().is_some() => {
+                    self.currently_editing = Some(CurrentlyEditing::Key)
+                }
+                Some(_) => {}
+            },
+            // null in one keystroke
+            KeyCode::Char('n') if control && kind.is_some() => self.save_edit(Some(Value::Null)),
+            KeyCode::Char(' ') if kind == Some(ValueKind::Boolean) => self.toggle_edited_boolean(),
+            KeyCode::Char('t') if control && kind == Some(ValueKind::Boolean) => {
+                self.toggle_edited_boolean()
+            }
+            // cancel, the node is left as it was
+            KeyCode::Esc => self.currently_editing = None,
+            KeyCode::F(2) => {
+                if let Err(e) = self.focused_buffer().toggle_escapes() {
+                    self.status = Some(e);
+                }
+            }
+            _ => {
+                self.focused_buffer().handle_key(key);
+            }
+        }
+    }
+
+    /// keys typed into a text input: the prompt, a table cell, or the edited key or value
+    fn handle_text_key(&mut self, key: KeyEvent) {
+        if let CurrentScreen::Editing = self.current_screen {
+            self.handle_edit_key(key);
+            return;
+        }
+        if key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        {
+            return;
+        }
+        match self.current_screen {
+            CurrentScreen::Prompt => match key.code {
+                KeyCode::Enter => {
+                    if let Some(mut prompt) = self.prompt.take() {
+                        self.current_screen = std::mem::take(&mut prompt.previous);
+                        self.run_prompt(prompt);
                     }
-                    KeyCode::Char('n') | KeyCode::Char('q') => {
-                        return Some(false);
+                }
+                KeyCode::Esc => {
+                    if let Some(prompt) = self.prompt.take() {
+                        self.current_screen = prompt.previous;
                     }
-                    _ => {}
-                },
-
-                CurrentScreen::Editing if key.kind == KeyEventKind::Press => match key.code {
-                    KeyCode::Up => match self.json_container.input_cursor {
-                        Some(current_input) => {
-                            if current_input == 1 {
-                                return None;
-                            } else {
-                                self.json_container.input_cursor =
-                                    Some(current_input.saturating_sub(1));
-                            }
-                        }
-                        None => {
-                            self.json_container.input_cursor = Some(1);
-                        }
-                    },
-                    KeyCode::Down => match self.json_container.input_cursor {
-                        Some(current_input) => match self.json_container.max_cursor {
-                            Some(max_cursor) => {
-                                if current_input == max_cursor {
-                                    return None;
-                                }
-                                self.json_container.input_cursor =
-                                    Some(current_input.saturating_add(1));
-                            }
-                            None => {
-                                return None;
-                            }
-                        },
-                        None => match self.json_container.max_cursor {
-                            Some(_max_cursor) => {
-                                self.json_container.input_cursor = Some(1);
-                            }
-                            None => {
-                                return None;
-                            }
-                        },
-                    },
-                    KeyCode::Left => {
-                        self.current_screen = CurrentScreen::Main;
-                        self.currently_editing = None;
-                        self.reset_cursor();
+                }
+                KeyCode::Backspace => {
+                    if let Some(prompt) = &mut self.prompt {
+                        prompt.input.pop();
                     }
-                    KeyCode::Tab => {
-                        self.current_screen = CurrentScreen::Main;
-                        self.currently_editing = None;
+                }
+                KeyCode::Char(value) => {
+                    if let Some(prompt) = &mut self.prompt {
+                        prompt.input.push(value);
                     }
-                    KeyCode::Enter => {
-                        if let Some(editing) = &self.currently_editing {
-                            match editing {
-                                CurrentlyEditing::Key => {
-                                    self.currently_editing = Some(CurrentlyEditing::Value);
-                                }
-                                CurrentlyEditing::Value => {
-                                    // self.save_key_value();
-                                    self.current_screen = CurrentScreen::Main;
-                                }
-                            }
-                        }
+                }
+                _ => {}
+            },
+            CurrentScreen::Table => {
+                let Some(table) = &mut self.table else {
+                    return;
+                };
+                let Some(input) = &mut table.cell_input else {
+                    return;
+                };
+                match key.code {
+                    KeyCode::Enter => self.commit_table_cell(),
+                    KeyCode::Esc => table.cell_input = None,
+                    KeyCode::Backspace => {
+                        input.pop();
                     }
+                    KeyCode::Char(value) => input.push(value),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
 
-                    KeyCode::Backspace => {
-                        if let Some(editing) = &self.currently_editing {
-                            match editing {
-                                CurrentlyEditing::Key => {
-                                    self.json_container.input_buffer.pop();
-                                }
-                                CurrentlyEditing::Value => {
-                                    self.json_container.input_buffer.pop();
-                                }
-                            }
-                        }
+    fn move_up(&mut self) {
+        match self.current_screen {
+            CurrentScreen::Main => match self.index_edition {
+                Some(val) => {
+                    self.index_edition = Some(val.saturating_sub(1));
+                }
+                None => {
+                    self.index_edition = Some(0);
+                }
+            },
+            CurrentScreen::Editing => match self.json_container.input_cursor {
+                Some(current_input) => {
+                    if current_input > 1 {
+                        self.json_container.input_cursor = Some(current_input.saturating_sub(1));
                     }
-                    KeyCode::Esc => {
-                        self.current_screen = CurrentScreen::Main;
-                        self.currently_editing = None;
+                }
+                None => {
+                    self.json_container.input_cursor = Some(1);
+                }
+            },
+            CurrentScreen::Table => {
+                if let Some(table) = &mut self.table {
+                    table.move_row(false);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn move_down(&mut self) {
+        match self.current_screen {
+            CurrentScreen::Main => match (self.index_edition, self.json_container.len()) {
+                (Some(val), Some(len)) if val + 1 < len => {
+                    self.index_edition = Some(val.saturating_add(1));
+                }
+                (None, Some(_len)) => {
+                    self.index_edition = Some(0);
+                }
+                _ => {}
+            },
+            CurrentScreen::Editing => {
+                match (
+                    self.json_container.input_cursor,
+                    self.json_container.max_cursor,
+                ) {
+                    (Some(current_input), Some(max_cursor)) if current_input < max_cursor => {
+                        self.json_container.input_cursor = Some(current_input.saturating_add(1));
                     }
-                    KeyCode::Char(value) => {
-                        if let Some(editing) = &self.currently_editing {
-                            match editing {
-                                CurrentlyEditing::Key => {
-                                    self.json_container.input_buffer.push(value);
-                                }
-                                CurrentlyEditing::Value => {
-                                    self.json_container.input_buffer.push(value);
-                                }
-                            }
-                        }
+                    (None, Some(_max_cursor)) => {
+                        self.json_container.input_cursor = Some(1);
                     }
-
                     _ => {}
-                },
-                _ => {}
+                }
+            }
+            CurrentScreen::Table => {
+                if let Some(table) = &mut self.table {
+                    table.move_row(true);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn move_to_end(&mut self, last: bool) {
+        match self.current_screen {
+            CurrentScreen::Main => {
+                self.index_edition = match self.json_container.len() {
+                    Some(len) if len > 0 => Some(if last { len - 1 } else { 0 }),
+                    _ => None,
+                };
+            }
+            CurrentScreen::Editing => {
+                self.json_container.input_cursor = match self.json_container.max_cursor {
+                    Some(max_cursor) if max_cursor > 0 => Some(if last { max_cursor } else { 1 }),
+                    _ => None,
+                };
+            }
+            CurrentScreen::Table => {
+                if let Some(table) = &mut self.table {
+                    table.move_row_to_end(last);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// run an action, returns `Some` when the app should exit, with whether to output the json
+    pub fn run_action(&mut self, action: Action) -> Option<bool> {
+        let edits_cell = action == Action::EnterEdit && self.table.is_some();
+        if (action.edits() || edits_cell) && self.read_only() {
+            return None;
+        }
+        match action {
+            Action::MoveUp => self.move_up(),
+            Action::MoveDown => self.move_down(),
+            Action::MoveTop => self.move_to_end(false),
+            Action::MoveBottom => self.move_to_end(true),
+            Action::MoveLeft | Action::MoveRight => {
+                if let Some(table) = &mut self.table {
+                    table.move_column(action == Action::MoveRight);
+                }
+            }
+            Action::EnterEdit => match self.current_screen {
+                CurrentScreen::Table => self.edit_table_cell(),
+                _ => self.current_screen = CurrentScreen::Editing,
+            },
+            Action::ExitEdit => {
+                self.current_screen = CurrentScreen::Main;
+                self.currently_editing = None;
+            }
+            Action::Back => {
+                self.current_screen = CurrentScreen::Main;
+                self.currently_editing = None;
+                self.reset_cursor();
+            }
+            Action::Commit => self.start_editing(),
+            Action::Quit if self.readonly => return Some(false),
+            Action::Quit => self.current_screen = CurrentScreen::Exiting,
+            Action::QuitAndWrite if self.save_blocked() => {
+                self.current_screen = CurrentScreen::Main;
+            }
+            Action::QuitAndWrite => return Some(true),
+            Action::QuitWithoutWriting => return Some(false),
+            Action::Save => self.save(),
+            Action::Delete => self.delete_selected(),
+            Action::Yank => self.yank_selected(),
+            Action::Paste => self.paste_after_selected(),
+            Action::ExportCsv => {
+                let input = format!("{}.csv", self.input_stem());
+                self.open_prompt(PromptKind::ExportCsv, input);
+            }
+            Action::ImportCsv => {
+                self.open_prompt(PromptKind::ImportCsv { infer_types: true }, String::new());
+            }
+            Action::ImportCsvRaw => {
+                self.open_prompt(PromptKind::ImportCsv { infer_types: false }, String::new());
+            }
+            Action::ToggleTable => match self.table {
+                Some(_) => self.close_table(),
+                None => self.open_table(),
+            },
+            Action::SortColumn => self.sort_table(),
+            Action::Help => self.open_help(),
+            Action::Palette => self.open_palette(),
+            Action::CopyPath => self.copy_selected_path(),
+            Action::EditExternal => self.external_edit = Some(self.selected_pointer()),
+            Action::ConvertType => self.open_convert(),
+            Action::SchemaErrors => self.open_error_panel(),
+            Action::AddMember => self.add_member(),
+            Action::ShowChanges => self.open_diff(),
+            Action::ExportPatch => {
+                let input = format!("{}.patch.json", self.input_stem());
+                self.open_prompt(PromptKind::ExportPatch, input);
+            }
+            Action::ApplyPatch => self.open_prompt(PromptKind::ApplyPatch, String::new()),
+            Action::Query => self.open_query(),
+            Action::MergeFile => self.open_prompt(PromptKind::MergeFile, String::new()),
+            Action::ExportMergePatch => {
+                let input = format!("{}.merge.json", self.input_stem());
+                self.open_prompt(PromptKind::ExportMergePatch, input);
+            }
+            Action::ExportSchema => {
+                let input = format!("{}.schema.json", self.input_stem());
+                self.open_prompt(PromptKind::ExportSchema, input);
+            }
+            Action::RenameKey => match self.selected_key() {
+                Some(key) => self.open_prompt(PromptKind::RenameKey, key),
+                None => self.status = Some("Only object members have a key".to_string()),
+            },
+            Action::MoveNodeUp => self.move_selected(false),
+            Action::MoveNodeDown => self.move_selected(true),
+            Action::SortKeys => self.sort_selected_keys(false),
+            Action::SortKeysRecursive => self.sort_selected_keys(true),
+            Action::SortArray => self.open_prompt(PromptKind::SortArray, String::new()),
+            Action::SaveAs => {
+                let input = self.input_path().unwrap_or_default().to_string();
+                self.open_prompt(PromptKind::SaveAs, input);
+            }
+            Action::GoToPath => {
+                let input = self.selected_pointer();
+                self.open_prompt(PromptKind::GoToPath, input);
+            }
+            Action::GrowColumn | Action::ShrinkColumn => {
+                if let Some(table) = &mut self.table {
+                    table.resize_column(action == Action::GrowColumn);
+                }
             }
         }
         None
@@ -1,6 +1,12 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let input_file = std::env::args().nth(1);
-    json_editor::cli_main(input_file)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match json_editor::cli_args(&args) {
+        json_editor::Start::Run {
+            input_file,
+            options,
+        } => json_editor::cli_main_with(input_file, options),
+        json_editor::Start::Exit(code) => std::process::exit(code),
+    }
 }
 
 #[cfg(test)]
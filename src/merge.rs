@@ -0,0 +1,209 @@
+use serde_json::{Map, Value};
+
+use crate::{
+    app::App,
+    diff::{diff_rows, DiffOptions, DiffView},
+    json::escape_pointer_token,
+    patch::{Operation, PatchPreview},
+    schema::same,
+};
+
+/// apply a JSON Merge Patch (RFC 7396) to `target` at `pointer`, a null member deletes its
+/// key, the equivalent JSON Patch operations are pushed to `operations`
+pub fn merge_patch(
+    target: &mut Value,
+    patch: &Value,
+    pointer: &str,
+    operations: &mut Vec<Operation>,
+) {
+    let Value::Object(members) = patch else {
+        if !same(target, patch) {
+            operations.push(Operation::Replace {
+                path: pointer.to_string(),
+                value: patch.clone(),
+            });
+        }
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+        operations.push(Operation::Replace {
+            path: pointer.to_string(),
+            value: target.clone(),
+        });
+    }
+    let Value::Object(obj) = target else {
+        return;
+    };
+    for (key, value) in members {
+        let path = format!("{}/{}", pointer, escape_pointer_token(key));
+        if value.is_null() {
+            if obj.shift_remove(key).is_some() {
+                operations.push(Operation::Remove { path });
+            }
+        } else if let Some(current) = obj.get_mut(key) {
+            merge_patch(current, value, &path, operations);
+        } else {
+            let mut added = Value::Null;
+            merge_patch(&mut added, value, &path, &mut Vec::new());
+            operations.push(Operation::Add {
+                path,
+                value: added.clone(),
+            });
+            obj.insert(key.clone(), added);
+        }
+    }
+}
+
+/// the smallest merge patch turning `old` into `new`, objects are patched member by member
+/// and everything else is replaced whole
+pub fn merge_diff(old: &Value, new: &Value) -> Value {
+    let (Value::Object(old), Value::Object(new)) = (old, new) else {
+        return new.clone();
+    };
+    let mut patch = Map::new();
+    for key in old.keys().filter(|key| !new.contains_key(*key)) {
+        patch.insert(key.clone(), Value::Null);
+    }
+    for (key, value) in new {
+        match old.get(key) {
+            Some(previous) if same(previous, value) => {}
+            Some(previous) => {
+                patch.insert(key.clone(), merge_diff(previous, value));
+            }
+            None => {
+                patch.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    Value::Object(patch)
+}
+
+impl App<'_> {
+    /// read a merge patch and preview the changes it makes
+    pub fn open_merge(&mut self, path: &str) {
+        let result = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| serde_json::from_str::<Value>(&text).map_err(|e| e.to_string()));
+        let patch = match result {
+            Ok(patch) => patch,
+            Err(e) => {
+                self.status = Some(format!("Cannot merge {}: {}", path, e));
+                return;
+            }
+        };
+        let mut patched = self.json_container.inner.clone();
+        let mut operations = Vec::new();
+        merge_patch(&mut patched, &patch, "", &mut operations);
+        if operations.is_empty() {
+            self.status = Some(format!("Merging {} changes nothing", path));
+            return;
+        }
+        let mut view = DiffView {
+            rows: diff_rows(
+                &self.json_container.inner,
+                &patched,
+                &DiffOptions::default(),
+            ),
+            selected: 0,
+            side_by_side: false,
+        };
+        let first = view.changes().next();
+        view.selected = first.unwrap_or_default();
+        self.patch_preview = Some(PatchPreview {
+            source: path.to_string(),
+            operations,
+            patched,
+            view,
+            merge: true,
+        });
+    }
+
+    /// write the merge patch from the loaded document to the edited one
+    pub fn export_merge_patch(&mut self, path: &str) {
+        let edited = &self.json_container.inner;
+        if same(&self.original, edited) {
+            self.status = Some("No edits to export".to_string());
+            return;
+        }
+        let patch = merge_diff(&self.original, edited);
+        // null values cannot be set by a merge patch, they delete their key
+        let mut check = self.original.clone();
+        merge_patch(&mut check, &patch, "", &mut Vec::new());
+        let lossy = !same(&check, edited);
+        let result = serde_json::to_string_pretty(&patch)
+            .map_err(|e| e.to_string())
+            .and_then(|text| std::fs::write(path, text + "\n").map_err(|e| e.to_string()));
+        self.status = Some(match result {
+            Ok(()) if lossy => format!(
+                "Exported the merge patch to {}, null values were dropped as it cannot set them",
+                path
+            ),
+            Ok(()) => format!("Exported the merge patch to {}", path),
+            Err(e) => format!("Export failed: {}", e),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_merge_patch() {
+        // the example of RFC 7396
+        let mut document = json!({
+            "title": "Goodbye!",
+            "author": {"givenName": "John", "familyName": "Doe"},
+            "tags": ["example", "sample"],
+            "content": "This will be unchanged"
+        });
+        let patch = json!({
+            "title": "Hello!",
+            "phoneNumber": "+01-123-456-7890",
+            "author": {"familyName": null},
+            "tags": ["example"]
+        });
+        let original = document.clone();
+        let mut operations = Vec::new();
+        merge_patch(&mut document, &patch, "", &mut operations);
+        let expected = json!({
+            "title": "Hello!",
+            "author": {"givenName": "John"},
+            "tags": ["example"],
+            "content": "This will be unchanged",
+            "phoneNumber": "+01-123-456-7890"
+        });
+        assert_eq!(document, expected);
+        assert_eq!(
+            crate::patch::apply_patch(&original, &operations).unwrap(),
+            expected
+        );
+        assert_eq!(
+            operations[2],
+            Operation::Remove {
+                path: "/author/familyName".into()
+            }
+        );
+
+        let diff = merge_diff(&original, &expected);
+        assert_eq!(diff, patch);
+        let mut scalar = json!(1);
+        merge_patch(
+            &mut scalar,
+            &json!({"a": {"b": null, "c": 2}}),
+            "",
+            &mut Vec::new(),
+        );
+        assert_eq!(scalar, json!({"a": {"c": 2}}));
+
+        // integers beyond f64 precision still differ
+        let big: Value = serde_json::from_str(r#"{"id": 9007199254740993, "n": 1}"#).unwrap();
+        let bigger: Value = serde_json::from_str(r#"{"id": 9007199254740992, "n": 1.0}"#).unwrap();
+        let diff = merge_diff(&big, &bigger);
+        assert_eq!(diff.to_string(), r#"{"id":9007199254740992}"#);
+    }
+}
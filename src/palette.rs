@@ -0,0 +1,159 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::{Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::{
+    app::App,
+    keymap::{Action, Context},
+    ui::centered_rect,
+};
+
+/// the command palette: a fuzzy search over the actions of the main screen
+#[derive(Default)]
+pub struct Palette {
+    pub input: String,
+    pub state: ListState,
+}
+
+/// score how well `pattern` matches `text` as a subsequence, `None` when it does not;
+/// consecutive characters and characters at the start of a word score higher
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for c in pattern
+        .to_lowercase()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+    {
+        let found = text[position..].iter().position(|t| *t == c)? + position;
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        score -= (found - position) as i64 / 4;
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+impl Palette {
+    /// the actions matching the input, best first
+    pub fn matches(&self) -> Vec<Action> {
+        let mut matches: Vec<(i64, Action)> = Action::ALL
+            .iter()
+            .filter(|action| action.in_palette())
+            .filter_map(|action| {
+                let name_score = fuzzy_score(&self.input, action.name());
+                let description_score = fuzzy_score(&self.input, action.description());
+                name_score
+                    .max(description_score)
+                    .map(|score| (score, *action))
+            })
+            .collect();
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        matches.into_iter().map(|(_, action)| action).collect()
+    }
+}
+
+impl App<'_> {
+    pub fn open_palette(&mut self) {
+        let mut palette = Palette::default();
+        palette.state.select(Some(0));
+        self.palette = Some(palette);
+    }
+
+    pub fn handle_palette_key(&mut self, key: KeyEvent) -> Option<bool> {
+        let palette = self.palette.as_mut()?;
+        match key.code {
+            KeyCode::Esc => self.palette = None,
+            KeyCode::Up => palette.state.select_previous(),
+            KeyCode::Down => palette.state.select_next(),
+            KeyCode::Enter => {
+                let matches = palette.matches();
+                let selected = palette.state.selected().unwrap_or_default();
+                self.palette = None;
+                if let Some(action) = matches.get(selected.min(matches.len().saturating_sub(1))) {
+                    return self.run_action(*action);
+                }
+            }
+            KeyCode::Backspace => {
+                palette.input.pop();
+                palette.state.select(Some(0));
+            }
+            KeyCode::Char(value) => {
+                palette.input.push(value);
+                palette.state.select(Some(0));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    pub fn render_palette(&mut self, frame: &mut Frame) {
+        let Some(palette) = &mut self.palette else {
+            return;
+        };
+        let area = centered_rect(60, 50, frame.area());
+        frame.render_widget(Clear, area);
+        let block = Block::default()
+            .title("Command palette")
+            .borders(Borders::ALL)
+            .style(self.theme.popup);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(2), Constraint::Min(1)])
+            .split(inner);
+
+        let input = Line::from(vec![
+            Span::styled("> ", self.theme.hint),
+            Span::raw(palette.input.clone()),
+        ]);
+        frame.render_widget(Paragraph::new(input), chunks[0]);
+        frame.set_cursor_position((
+            chunks[0].x + 2 + palette.input.chars().count() as u16,
+            chunks[0].y,
+        ));
+
+        let items: Vec<ListItem> = palette
+            .matches()
+            .into_iter()
+            .map(|action| {
+                let keys = self
+                    .keymap
+                    .key_for(Context::Main, action)
+                    .unwrap_or_default();
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{:40}", action.description())),
+                    Span::styled(keys, self.theme.hint),
+                ]))
+            })
+            .collect();
+        let list = List::new(items).highlight_style(self.theme.cursor);
+        frame.render_stateful_widget(list, chunks[1], &mut palette.state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("sva", "save-as").is_some());
+        assert!(fuzzy_score("xyz", "save-as").is_none());
+        assert!(fuzzy_score("save", "save-as") > fuzzy_score("save", "sort-values"));
+    }
+}
@@ -0,0 +1,313 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    app::App,
+    diff::{diff_rows, DiffOptions, DiffView},
+    edit::{display_pointer, split_pointer},
+    schema::same,
+};
+
+/// an operation of a JSON Patch (RFC 6902)
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Operation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+impl Operation {
+    fn describe(&self) -> String {
+        let (name, path) = match self {
+            Operation::Add { path, .. } => ("add", path),
+            Operation::Remove { path } => ("remove", path),
+            Operation::Replace { path, .. } => ("replace", path),
+            Operation::Move { path, .. } => ("move", path),
+            Operation::Copy { path, .. } => ("copy", path),
+            Operation::Test { path, .. } => ("test", path),
+        };
+        format!("{} {}", name, display_pointer(path))
+    }
+}
+
+fn missing(pointer: &str) -> String {
+    format!("no node at {}", display_pointer(pointer))
+}
+
+/// an array index of a patch: digits without leading zeros, below `bound`
+fn index(token: &str, bound: usize) -> Result<usize, String> {
+    let valid = token == "0" || (!token.starts_with('0') && !token.is_empty());
+    match token.parse::<usize>() {
+        Ok(idx) if valid && idx < bound => Ok(idx),
+        _ => Err(format!("invalid array index \"{}\"", token)),
+    }
+}
+
+fn add(document: &mut Value, path: &str, value: Value) -> Result<(), String> {
+    let Some((parent, token)) = split_pointer(path) else {
+        *document = value;
+        return Ok(());
+    };
+    match document.pointer_mut(parent) {
+        Some(Value::Object(obj)) => {
+            obj.insert(token, value);
+            Ok(())
+        }
+        Some(Value::Array(array)) => {
+            let idx = match token.as_str() {
+                "-" => array.len(),
+                token => index(token, array.len() + 1)?,
+            };
+            array.insert(idx, value);
+            Ok(())
+        }
+        Some(_) => Err(format!("{} is not a container", display_pointer(parent))),
+        None => Err(missing(parent)),
+    }
+}
+
+fn remove(document: &mut Value, path: &str) -> Result<Value, String> {
+    let (parent, token) = split_pointer(path).ok_or("cannot remove the root")?;
+    match document.pointer_mut(parent) {
+        Some(Value::Object(obj)) => obj.shift_remove(&token).ok_or_else(|| missing(path)),
+        Some(Value::Array(array)) => {
+            let idx = index(&token, array.len())?;
+            Ok(array.remove(idx))
+        }
+        _ => Err(missing(path)),
+    }
+}
+
+pub fn apply_operation(document: &mut Value, operation: &Operation) -> Result<(), String> {
+    match operation {
+        Operation::Add { path, value } => add(document, path, value.clone()),
+        Operation::Remove { path } => remove(document, path).map(|_| ()),
+        Operation::Replace { path, value } => {
+            *document.pointer_mut(path).ok_or_else(|| missing(path))? = value.clone();
+            Ok(())
+        }
+        Operation::Move { from, path } => {
+            if path.starts_with(&format!("{}/", from)) {
+                return Err("cannot move a node into itself".to_string());
+            }
+            let value = remove(document, from)?;
+            add(document, path, value)
+        }
+        Operation::Copy { from, path } => {
+            let value = document.pointer(from).ok_or_else(|| missing(from))?.clone();
+            add(document, path, value)
+        }
+        Operation::Test { path, value } => match document.pointer(path) {
+            Some(actual) if same(actual, value) => Ok(()),
+            Some(actual) => Err(format!("found {}, expected {}", actual, value)),
+            None => Err(missing(path)),
+        },
+    }
+}
+
+/// apply every operation of `patch` to `document`, or none of them if one fails
+pub fn apply_patch(document: &Value, patch: &[Operation]) -> Result<Value, String> {
+    let mut patched = document.clone();
+    for (idx, operation) in patch.iter().enumerate() {
+        apply_operation(&mut patched, operation).map_err(|e| {
+            format!(
+                "operation {} ({}) failed: {}",
+                idx + 1,
+                operation.describe(),
+                e
+            )
+        })?;
+    }
+    Ok(patched)
+}
+
+/// a patch waiting to be applied, with its changes
+pub struct PatchPreview {
+    pub source: String,
+    pub operations: Vec<Operation>,
+    pub patched: Value,
+    pub view: DiffView,
+    pub merge: bool, // a merge patch, the operations are the ones it amounts to
+}
+
+impl App<'_> {
+    /// keep an edit of the session, to export the edits as a patch
+    pub fn record(&mut self, operation: Operation) {
+        self.patch.push(operation);
+    }
+
+    pub fn export_patch(&mut self, path: &str) {
+        if self.patch.is_empty() {
+            self.status = Some("No edits to export".to_string());
+            return;
+        }
+        let result = serde_json::to_string_pretty(&self.patch)
+            .map_err(|e| e.to_string())
+            .and_then(|text| std::fs::write(path, text + "\n").map_err(|e| e.to_string()));
+        self.status = Some(match result {
+            Ok(()) => format!("Exported {} operation(s) to {}", self.patch.len(), path),
+            Err(e) => format!("Export failed: {}", e),
+        });
+    }
+
+    /// read a patch and preview the changes it makes, a failing `test` cancels it
+    pub fn open_patch(&mut self, path: &str) {
+        let result = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| {
+                serde_json::from_str::<Vec<Operation>>(&text).map_err(|e| e.to_string())
+            })
+            .and_then(|operations| {
+                let patched = apply_patch(&self.json_container.inner, &operations)?;
+                Ok((operations, patched))
+            });
+        let (operations, patched) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                self.status = Some(format!("Cannot apply {}: {}", path, e));
+                return;
+            }
+        };
+        let mut view = DiffView {
+            rows: diff_rows(
+                &self.json_container.inner,
+                &patched,
+                &DiffOptions::default(),
+            ),
+            selected: 0,
+            side_by_side: false,
+        };
+        let first = view.changes().next();
+        view.selected = first.unwrap_or_default();
+        self.patch_preview = Some(PatchPreview {
+            source: path.to_string(),
+            operations,
+            patched,
+            view,
+            merge: false,
+        });
+    }
+
+    pub fn handle_patch_preview_key(&mut self, key: KeyEvent) {
+        let Some(preview) = &mut self.patch_preview else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('n') => self.patch_preview = None,
+            KeyCode::Down | KeyCode::Char('j') => preview.view.step(true),
+            KeyCode::Up | KeyCode::Char('k') => preview.view.step(false),
+            KeyCode::Tab => preview.view.side_by_side = !preview.view.side_by_side,
+            KeyCode::Enter | KeyCode::Char('y') => {
+                let Some(preview) = self.patch_preview.take() else {
+                    return;
+                };
+                self.json_container.inner = preview.patched;
+                self.json_container.mark_dirty();
+                self.reset_cursor();
+                let count = preview.operations.len();
+                self.patch.extend(
+                    preview
+                        .operations
+                        .into_iter()
+                        .filter(|operation| !matches!(operation, Operation::Test { .. })),
+                );
+                self.status = Some(match preview.merge {
+                    true => format!("Merged {}", preview.source),
+                    false => format!("Applied {} operation(s) of {}", count, preview.source),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    pub fn render_patch_preview(&self, frame: &mut Frame) {
+        let Some(preview) = &self.patch_preview else {
+            return;
+        };
+        let changes = preview.view.changes().count();
+        let title = match preview.merge {
+            true => format!("Merge {}: {} change(s)", preview.source, changes),
+            false => format!(
+                "Apply {}: {} operation(s), {} change(s)",
+                preview.source,
+                preview.operations.len(),
+                changes
+            ),
+        };
+        let keys = "Enter to apply, Esc to cancel, Up/Down between changes, Tab for the layout";
+        self.render_diff_view(frame, &preview.view, title, keys, ["Current", "Patched"]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_apply_patch() {
+        let document = json!({"a": {"b": [1, 2]}, "c": "x"});
+        let patch: Vec<Operation> = serde_json::from_value(json!([
+            {"op": "test", "path": "/a/b/0", "value": 1.0},
+            {"op": "add", "path": "/a/b/-", "value": 3},
+            {"op": "remove", "path": "/a/b/0"},
+            {"op": "replace", "path": "/c", "value": "y"},
+            {"op": "copy", "from": "/a/b", "path": "/d"},
+            {"op": "move", "from": "/c", "path": "/a/c"}
+        ]))
+        .unwrap();
+        assert_eq!(
+            apply_patch(&document, &patch).unwrap(),
+            json!({"a": {"b": [2, 3], "c": "y"}, "d": [2, 3]})
+        );
+
+        let failing: Vec<Operation> = serde_json::from_value(json!([
+            {"op": "replace", "path": "/c", "value": "y"},
+            {"op": "test", "path": "/c", "value": "x"}
+        ]))
+        .unwrap();
+        assert_eq!(
+            apply_patch(&document, &failing),
+            Err("operation 2 (test /c) failed: found \"y\", expected \"x\"".to_string())
+        );
+        let big: Value = serde_json::from_str(r#"{"id": 9007199254740993}"#).unwrap();
+        let test: Vec<Operation> =
+            serde_json::from_str(r#"[{"op": "test", "path": "/id", "value": 9007199254740992}]"#)
+                .unwrap();
+        assert!(apply_patch(&big, &test).is_err());
+        // members an operation does not define are ignored
+        let extra: Vec<Operation> = serde_json::from_value(json!([
+            {"op": "remove", "path": "/c", "comment": "x"},
+            {"op": "add", "path": "/e", "value": 1, "from": "/a"}
+        ]))
+        .unwrap();
+        assert_eq!(
+            apply_patch(&document, &extra).unwrap(),
+            json!({"a": {"b": [1, 2]}, "e": 1})
+        );
+        assert!(apply_patch(
+            &document,
+            &[Operation::Remove {
+                path: "/a/b/01".into()
+            }]
+        )
+        .is_err());
+        assert_eq!(
+            serde_json::to_value(Operation::Move {
+                from: "/a".into(),
+                path: "/b".into()
+            })
+            .unwrap(),
+            json!({"op": "move", "from": "/a", "path": "/b"})
+        );
+    }
+}
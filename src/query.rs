@@ -0,0 +1,851 @@
+use std::cmp::Ordering;
+
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use serde_json::Value;
+
+use crate::{
+    app::{App, PromptKind},
+    edit::display_pointer,
+    json::{compare_numbers, escape_pointer_token},
+    schema::{same, type_name},
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    And,
+    Or,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Dot,
+    Recurse,
+    Name(String),
+    Literal(Value),
+    Operator(Operator),
+    Open(char),
+    Close(char),
+    Pipe,
+    Comma,
+    Question,
+}
+
+/// a filter of the jq subset
+#[derive(Clone, PartialEq, Debug)]
+enum Filter {
+    Identity,
+    Recurse,
+    Literal(Value),
+    Field(Box<Filter>, String),
+    Index(Box<Filter>, i64),
+    Iterate(Box<Filter>),
+    Optional(Box<Filter>),
+    Collect(Box<Filter>),
+    Pipe(Box<Filter>, Box<Filter>),
+    Comma(Box<Filter>, Box<Filter>),
+    Binary(Box<Filter>, Operator, Box<Filter>),
+    Select(Box<Filter>),
+    Keys,
+    Length,
+    Not,
+    Type,
+    Empty,
+}
+
+/// a result of a query, with its place in the document when it is one of its nodes
+#[derive(Clone, PartialEq, Debug)]
+pub struct Item {
+    pub value: Value,
+    pub pointer: Option<String>,
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < chars.len() {
+        let c = chars[pos];
+        let next = chars.get(pos + 1).copied();
+        let start = pos;
+        pos += 1;
+        let token = match (c, next) {
+            (c, _) if c.is_whitespace() => continue,
+            ('.', Some('.')) => {
+                pos += 1;
+                Token::Recurse
+            }
+            ('.', _) => Token::Dot,
+            ('"', _) => {
+                let mut escaped = false;
+                while pos < chars.len() && (escaped || chars[pos] != '"') {
+                    escaped = !escaped && chars[pos] == '\\';
+                    pos += 1;
+                }
+                if pos == chars.len() {
+                    return Err("unterminated string".to_string());
+                }
+                pos += 1;
+                let text: String = chars[start..pos].iter().collect();
+                Token::Literal(serde_json::from_str(&text).map_err(|e| e.to_string())?)
+            }
+            (c, _)
+                if c.is_ascii_digit() || (c == '-' && next.is_some_and(|n| n.is_ascii_digit())) =>
+            {
+                while pos < chars.len()
+                    && (chars[pos].is_ascii_alphanumeric() || "+-.".contains(chars[pos]))
+                {
+                    pos += 1;
+                }
+                let text: String = chars[start..pos].iter().collect();
+                match serde_json::from_str::<Value>(&text) {
+                    Ok(number @ Value::Number(_)) => Token::Literal(number),
+                    _ => return Err(format!("invalid number {}", text)),
+                }
+            }
+            (c, _) if c.is_alphabetic() || c == '_' => {
+                while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_') {
+                    pos += 1;
+                }
+                Token::Name(chars[start..pos].iter().collect())
+            }
+            ('=', Some('=')) | ('!', Some('=')) | ('<', Some('=')) | ('>', Some('=')) => {
+                pos += 1;
+                Token::Operator(match c {
+                    '=' => Operator::Equal,
+                    '!' => Operator::NotEqual,
+                    '<' => Operator::LessOrEqual,
+                    _ => Operator::GreaterOrEqual,
+                })
+            }
+            ('<', _) => Token::Operator(Operator::Less),
+            ('>', _) => Token::Operator(Operator::Greater),
+            ('(' | '[', _) => Token::Open(c),
+            (')' | ']', _) => Token::Close(c),
+            ('|', _) => Token::Pipe,
+            (',', _) => Token::Comma,
+            ('?', _) => Token::Question,
+            (c, _) => return Err(format!("unexpected character '{}'", c)),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// `?` after a path only ignores the errors of its last step, `.a[]?` still fails
+/// when `.a` does
+fn optional(filter: Filter) -> Filter {
+    let (inner, last) = match filter {
+        Filter::Field(inner, key) => (inner, Filter::Field(Box::new(Filter::Identity), key)),
+        Filter::Index(inner, idx) => (inner, Filter::Index(Box::new(Filter::Identity), idx)),
+        Filter::Iterate(inner) => (inner, Filter::Iterate(Box::new(Filter::Identity))),
+        filter => return Filter::Optional(Box::new(filter)),
+    };
+    Filter::Pipe(inner, Box::new(Filter::Optional(Box::new(last))))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), String> {
+        match self.next() {
+            Some(found) if found == token => Ok(()),
+            Some(found) => Err(format!("expected {:?}, found {:?}", token, found)),
+            None => Err(format!("expected {:?} at the end", token)),
+        }
+    }
+
+    fn pipe(&mut self) -> Result<Filter, String> {
+        let mut filter = self.comma()?;
+        while self.peek() == Some(&Token::Pipe) {
+            self.pos += 1;
+            filter = Filter::Pipe(Box::new(filter), Box::new(self.comma()?));
+        }
+        Ok(filter)
+    }
+
+    fn comma(&mut self) -> Result<Filter, String> {
+        let mut filter = self.binary(Operator::Or)?;
+        while self.peek() == Some(&Token::Comma) {
+            self.pos += 1;
+            filter = Filter::Comma(Box::new(filter), Box::new(self.binary(Operator::Or)?));
+        }
+        Ok(filter)
+    }
+
+    /// `or`, then `and`, then the comparisons, from the loosest to the tightest
+    fn binary(&mut self, level: Operator) -> Result<Filter, String> {
+        let (tighter, name) = match level {
+            Operator::Or => (Some(Operator::And), "or"),
+            Operator::And => (None, "and"),
+            _ => unreachable!(),
+        };
+        let operand = |parser: &mut Self| match tighter {
+            Some(tighter) => parser.binary(tighter),
+            None => parser.comparison(),
+        };
+        let mut filter = operand(self)?;
+        while self.peek() == Some(&Token::Name(name.to_string())) {
+            self.pos += 1;
+            filter = Filter::Binary(Box::new(filter), level, Box::new(operand(self)?));
+        }
+        Ok(filter)
+    }
+
+    fn comparison(&mut self) -> Result<Filter, String> {
+        let filter = self.postfix()?;
+        match self.peek() {
+            Some(Token::Operator(operator)) => {
+                let operator = *operator;
+                self.pos += 1;
+                Ok(Filter::Binary(
+                    Box::new(filter),
+                    operator,
+                    Box::new(self.postfix()?),
+                ))
+            }
+            _ => Ok(filter),
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Filter, String> {
+        let mut filter = self.term()?;
+        loop {
+            filter = match self.peek() {
+                Some(Token::Dot) => {
+                    self.pos += 1;
+                    match self.next() {
+                        Some(Token::Name(name)) => Filter::Field(Box::new(filter), name),
+                        Some(Token::Literal(Value::String(name))) => {
+                            Filter::Field(Box::new(filter), name)
+                        }
+                        Some(Token::Open('[')) => self.bracket(filter)?,
+                        _ => return Err("expected a key after '.'".to_string()),
+                    }
+                }
+                Some(Token::Open('[')) => {
+                    self.pos += 1;
+                    self.bracket(filter)?
+                }
+                Some(Token::Question) => {
+                    self.pos += 1;
+                    optional(filter)
+                }
+                _ => return Ok(filter),
+            };
+        }
+    }
+
+    /// what follows `[` after a filter: `[]`, `[0]` or `["key"]`
+    fn bracket(&mut self, filter: Filter) -> Result<Filter, String> {
+        let filter = Box::new(filter);
+        let filter = match self.next() {
+            Some(Token::Close(']')) => return Ok(Filter::Iterate(filter)),
+            Some(Token::Literal(Value::String(key))) => Filter::Field(filter, key),
+            Some(Token::Literal(Value::Number(number))) => match number.as_i64() {
+                Some(idx) => Filter::Index(filter, idx),
+                None => return Err(format!("invalid index {}", number)),
+            },
+            _ => return Err("expected an index, a key or ']' after '['".to_string()),
+        };
+        self.expect(Token::Close(']'))?;
+        Ok(filter)
+    }
+
+    fn term(&mut self) -> Result<Filter, String> {
+        match self.next() {
+            Some(Token::Dot) => match self.peek().cloned() {
+                Some(Token::Name(name)) => {
+                    self.pos += 1;
+                    Ok(Filter::Field(Box::new(Filter::Identity), name))
+                }
+                Some(Token::Literal(Value::String(name))) => {
+                    self.pos += 1;
+                    Ok(Filter::Field(Box::new(Filter::Identity), name))
+                }
+                _ => Ok(Filter::Identity),
+            },
+            Some(Token::Recurse) => Ok(Filter::Recurse),
+            Some(Token::Literal(value)) => Ok(Filter::Literal(value)),
+            Some(Token::Open('(')) => {
+                let filter = self.pipe()?;
+                self.expect(Token::Close(')'))?;
+                Ok(filter)
+            }
+            Some(Token::Open('[')) => {
+                if self.peek() == Some(&Token::Close(']')) {
+                    self.pos += 1;
+                    return Ok(Filter::Literal(Value::Array(Vec::new())));
+                }
+                let filter = self.pipe()?;
+                self.expect(Token::Close(']'))?;
+                Ok(Filter::Collect(Box::new(filter)))
+            }
+            Some(Token::Name(name)) => match name.as_str() {
+                "true" => Ok(Filter::Literal(Value::Bool(true))),
+                "false" => Ok(Filter::Literal(Value::Bool(false))),
+                "null" => Ok(Filter::Literal(Value::Null)),
+                "keys" => Ok(Filter::Keys),
+                "length" => Ok(Filter::Length),
+                "not" => Ok(Filter::Not),
+                "type" => Ok(Filter::Type),
+                "empty" => Ok(Filter::Empty),
+                "select" | "map" => {
+                    self.expect(Token::Open('('))?;
+                    let argument = Box::new(self.pipe()?);
+                    self.expect(Token::Close(')'))?;
+                    Ok(match name.as_str() {
+                        "select" => Filter::Select(argument),
+                        // map(f) is [.[] | f]
+                        _ => Filter::Collect(Box::new(Filter::Pipe(
+                            Box::new(Filter::Iterate(Box::new(Filter::Identity))),
+                            argument,
+                        ))),
+                    })
+                }
+                name => Err(format!("unknown function {}", name)),
+            },
+            Some(token) => Err(format!("unexpected {:?}", token)),
+            None => Err("unexpected end of the expression".to_string()),
+        }
+    }
+}
+
+/// parse a jq expression: paths (`.a.b`, `.[0]`, `.[]`, `..`), `|`, `,`, `[...]`, literals,
+/// comparisons, `and`/`or`, `select()`, `map()`, `keys`, `length`, `not`, `type`, `empty`
+fn parse(expression: &str) -> Result<Filter, String> {
+    let mut parser = Parser {
+        tokens: tokenize(expression)?,
+        pos: 0,
+    };
+    if parser.tokens.is_empty() {
+        return Ok(Filter::Identity);
+    }
+    let filter = parser.pipe()?;
+    match parser.next() {
+        None => Ok(filter),
+        Some(token) => Err(format!("unexpected {:?}", token)),
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+/// the order of jq: null, false, true, numbers, strings, arrays, objects
+fn compare(a: &Value, b: &Value) -> Ordering {
+    let rank = |value: &Value| match value {
+        Value::Null => 0,
+        Value::Bool(false) => 1,
+        Value::Bool(true) => 2,
+        Value::Number(_) => 3,
+        Value::String(_) => 4,
+        Value::Array(_) => 5,
+        Value::Object(_) => 6,
+    };
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => compare_numbers(a, b),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Array(a), Value::Array(b)) => a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| compare(a, b))
+            .find(|order| order.is_ne())
+            .unwrap_or(a.len().cmp(&b.len())),
+        // by their sorted keys, then by their values in the order of the keys
+        (Value::Object(a), Value::Object(b)) => {
+            let mut keys: Vec<&String> = a.keys().collect();
+            let mut other: Vec<&String> = b.keys().collect();
+            keys.sort();
+            other.sort();
+            keys.cmp(&other).then_with(|| {
+                keys.iter()
+                    .map(|key| compare(&a[*key], &b[*key]))
+                    .find(|order| order.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        _ if same(a, b) => Ordering::Equal,
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn child(item: &Item, token: &str, value: Value) -> Item {
+    Item {
+        value,
+        pointer: item
+            .pointer
+            .as_ref()
+            .map(|pointer| format!("{}/{}", pointer, escape_pointer_token(token))),
+    }
+}
+
+fn computed(value: Value) -> Item {
+    Item {
+        value,
+        pointer: None,
+    }
+}
+
+fn descendants(item: Item, items: &mut Vec<Item>) {
+    let children: Vec<Item> = match &item.value {
+        Value::Array(array) => array
+            .iter()
+            .enumerate()
+            .map(|(idx, value)| child(&item, &idx.to_string(), value.clone()))
+            .collect(),
+        Value::Object(obj) => obj
+            .iter()
+            .map(|(key, value)| child(&item, key, value.clone()))
+            .collect(),
+        _ => Vec::new(),
+    };
+    items.push(item);
+    for child in children {
+        descendants(child, items);
+    }
+}
+
+fn eval(filter: &Filter, item: Item) -> Result<Vec<Item>, String> {
+    let mut results = Vec::new();
+    match filter {
+        Filter::Identity => results.push(item),
+        Filter::Recurse => descendants(item, &mut results),
+        Filter::Literal(value) => results.push(computed(value.clone())),
+        Filter::Field(filter, key) => {
+            for item in eval(filter, item)? {
+                results.push(match &item.value {
+                    Value::Object(obj) => match obj.get(key) {
+                        Some(value) => child(&item, key, value.clone()),
+                        None => computed(Value::Null),
+                    },
+                    Value::Null => computed(Value::Null),
+                    value => {
+                        return Err(format!(
+                            "cannot index {} with \"{}\"",
+                            type_name(value),
+                            key
+                        ))
+                    }
+                });
+            }
+        }
+        Filter::Index(filter, idx) => {
+            for item in eval(filter, item)? {
+                results.push(match &item.value {
+                    Value::Array(array) => {
+                        let position = match *idx < 0 {
+                            true => array.len().checked_sub(idx.unsigned_abs() as usize),
+                            false => Some(*idx as usize),
+                        };
+                        match position.and_then(|position| Some((position, array.get(position)?))) {
+                            Some((position, value)) => {
+                                child(&item, &position.to_string(), value.clone())
+                            }
+                            None => computed(Value::Null),
+                        }
+                    }
+                    Value::Null => computed(Value::Null),
+                    value => {
+                        return Err(format!("cannot index {} with a number", type_name(value)))
+                    }
+                });
+            }
+        }
+        Filter::Iterate(filter) => {
+            for item in eval(filter, item)? {
+                match &item.value {
+                    Value::Array(array) => results.extend(
+                        array
+                            .iter()
+                            .enumerate()
+                            .map(|(idx, value)| child(&item, &idx.to_string(), value.clone())),
+                    ),
+                    Value::Object(obj) => results.extend(
+                        obj.iter()
+                            .map(|(key, value)| child(&item, key, value.clone())),
+                    ),
+                    value => return Err(format!("cannot iterate over {}", type_name(value))),
+                }
+            }
+        }
+        Filter::Optional(filter) => results = eval(filter, item).unwrap_or_default(),
+        Filter::Collect(filter) => {
+            let values = eval(filter, item)?.into_iter().map(|item| item.value);
+            results.push(computed(Value::Array(values.collect())));
+        }
+        Filter::Pipe(left, right) => {
+            for item in eval(left, item)? {
+                results.extend(eval(right, item)?);
+            }
+        }
+        Filter::Comma(left, right) => {
+            results = eval(left, item.clone())?;
+            results.extend(eval(right, item)?);
+        }
+        Filter::Binary(left, operator, right) => {
+            for right in eval(right, item.clone())? {
+                for left in eval(left, item.clone())? {
+                    let (left, right) = (&left.value, &right.value);
+                    let order = compare(left, right);
+                    let value = match operator {
+                        Operator::Equal => same(left, right),
+                        Operator::NotEqual => !same(left, right),
+                        Operator::Less => order.is_lt(),
+                        Operator::LessOrEqual => order.is_le(),
+                        Operator::Greater => order.is_gt(),
+                        Operator::GreaterOrEqual => order.is_ge(),
+                        Operator::And => truthy(left) && truthy(right),
+                        Operator::Or => truthy(left) || truthy(right),
+                    };
+                    results.push(computed(Value::Bool(value)));
+                }
+            }
+        }
+        Filter::Select(condition) => {
+            for value in eval(condition, item.clone())? {
+                if truthy(&value.value) {
+                    results.push(item.clone());
+                }
+            }
+        }
+        Filter::Keys => results.push(computed(match &item.value {
+            Value::Object(obj) => {
+                let mut keys: Vec<&String> = obj.keys().collect();
+                keys.sort();
+                keys.into_iter().cloned().map(Value::String).collect()
+            }
+            Value::Array(array) => (0..array.len()).map(Value::from).collect(),
+            value => return Err(format!("{} has no keys", type_name(value))),
+        })),
+        Filter::Length => results.push(computed(match &item.value {
+            Value::Null => Value::from(0),
+            Value::String(text) => Value::from(text.chars().count()),
+            Value::Array(array) => Value::from(array.len()),
+            Value::Object(obj) => Value::from(obj.len()),
+            Value::Number(number) => {
+                let text = number.to_string();
+                let absolute = text.strip_prefix('-').unwrap_or(&text);
+                Value::Number(
+                    absolute
+                        .parse()
+                        .map_err(|e: serde_json::Error| e.to_string())?,
+                )
+            }
+            value => return Err(format!("{} has no length", type_name(value))),
+        })),
+        Filter::Not => results.push(computed(Value::Bool(!truthy(&item.value)))),
+        Filter::Type => results.push(computed(Value::from(type_name(&item.value)))),
+        Filter::Empty => {}
+    }
+    Ok(results)
+}
+
+/// run a jq expression on `input`, the node at `pointer` of the document
+pub fn run(expression: &str, input: &Value, pointer: &str) -> Result<Vec<Item>, String> {
+    let filter = parse(expression)?;
+    eval(
+        &filter,
+        Item {
+            value: input.clone(),
+            pointer: Some(pointer.to_string()),
+        },
+    )
+}
+
+/// a query on a node of the document, with its live results
+pub struct Query {
+    pub pointer: String,
+    pub expression: String,
+    pub revision: Option<u64>, // of the document when the results were computed
+    pub results: Result<Vec<Item>, String>,
+    pub selected: usize,
+}
+
+impl App<'_> {
+    /// query the selected node, or the document
+    pub fn open_query(&mut self) {
+        self.query = Some(Query {
+            pointer: self.selected_pointer(),
+            expression: String::new(),
+            revision: None,
+            results: Ok(Vec::new()),
+            selected: 0,
+        });
+        self.open_prompt(PromptKind::Query, self.last_query.clone());
+    }
+
+    /// evaluate the query again when the expression or the document changed
+    pub fn refresh_query(&mut self) {
+        let expression = match &self.prompt {
+            Some(prompt) if matches!(prompt.kind, PromptKind::Query) => prompt.input.clone(),
+            _ => match &self.query {
+                Some(query) => query.expression.clone(),
+                None => return,
+            },
+        };
+        if let Some(query) = &self.query {
+            if query.expression == expression
+                && query.revision == Some(self.json_container.revision)
+            {
+                return;
+            }
+        }
+        self.evaluate_query(expression);
+    }
+
+    fn evaluate_query(&mut self, expression: String) {
+        let revision = self.json_container.revision;
+        let Some(query) = &mut self.query else {
+            return;
+        };
+        query.results = match self.json_container.inner.pointer(&query.pointer) {
+            Some(input) => run(&expression, input, &query.pointer),
+            None => Err(format!("no node at {}", display_pointer(&query.pointer))),
+        };
+        query.expression = expression;
+        query.revision = Some(revision);
+        let count = query.results.as_ref().map_or(0, Vec::len);
+        query.selected = query.selected.min(count.saturating_sub(1));
+    }
+
+    /// the prompt was validated, its results can be browsed
+    pub fn run_query(&mut self, expression: &str) {
+        self.last_query = expression.to_string();
+        self.evaluate_query(expression.to_string());
+        if let Some(Err(e)) = self.query.as_ref().map(|query| &query.results) {
+            self.status = Some(format!("Query failed: {}", e));
+        }
+    }
+
+    pub fn handle_query_key(&mut self, key: KeyEvent) {
+        let Some(query) = &mut self.query else {
+            return;
+        };
+        let count = query.results.as_ref().map_or(0, Vec::len);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.query = None,
+            KeyCode::Down | KeyCode::Char('j') => {
+                query.selected = (query.selected + 1).min(count.saturating_sub(1))
+            }
+            KeyCode::Up | KeyCode::Char('k') => query.selected = query.selected.saturating_sub(1),
+            KeyCode::Char('e') | KeyCode::Char('|') => {
+                let expression = query.expression.clone();
+                self.open_prompt(PromptKind::Query, expression);
+            }
+            KeyCode::Enter => {
+                let pointer = query
+                    .results
+                    .as_ref()
+                    .ok()
+                    .and_then(|results| results.get(query.selected))
+                    .map(|item| item.pointer.clone());
+                match pointer {
+                    Some(Some(pointer)) => {
+                        self.query = None;
+                        self.select_pointer(&pointer);
+                        self.status = Some(format!("At {}", display_pointer(&pointer)));
+                    }
+                    Some(None) => {
+                        self.status = Some("This result is not a node of the document".into())
+                    }
+                    None => self.status = Some("No result selected".to_string()),
+                }
+            }
+            KeyCode::Char('r') if !self.read_only() => self.replace_with_query(),
+            _ => {}
+        }
+    }
+
+    /// replace the queried node with the result, or an array of the results
+    fn replace_with_query(&mut self) {
+        let Some(Query {
+            pointer,
+            results: Ok(results),
+            ..
+        }) = &self.query
+        else {
+            self.status = Some("No result to replace with".to_string());
+            return;
+        };
+        let pointer = pointer.clone();
+        let mut values: Vec<Value> = results.iter().map(|item| item.value.clone()).collect();
+        let value = match values.len() {
+            0 => {
+                self.status = Some("No result to replace with".to_string());
+                return;
+            }
+            1 => values.remove(0),
+            _ => Value::Array(values),
+        };
+        if self.replace_at(&pointer, value) {
+            self.query = None;
+            self.select_pointer(&pointer);
+            self.status = Some(format!(
+                "Replaced {} with the result",
+                display_pointer(&pointer)
+            ));
+        }
+    }
+
+    /// the results in the right pane, in place of the selected element
+    pub fn render_query(&mut self, frame: &mut Frame, area: Rect) {
+        self.refresh_query();
+        let Some(query) = &self.query else {
+            return;
+        };
+        let mut block = Block::default()
+            .title(format!(
+                "Query {} on {}",
+                query.expression,
+                display_pointer(&query.pointer)
+            ))
+            .borders(Borders::ALL)
+            .border_style(match self.prompt {
+                Some(_) => self.theme.border_inactive,
+                None => self.theme.border_active,
+            });
+        let mut lines = Vec::new();
+        let mut selected_line = 0;
+        match &query.results {
+            Ok(results) => {
+                block = block.title_bottom(Line::styled(
+                    format!(
+                        "{} result(s), Enter jump, r replace, e edit, Esc close",
+                        results.len()
+                    ),
+                    self.theme.hint,
+                ));
+                for (idx, item) in results.iter().enumerate() {
+                    let selected = idx == query.selected && self.prompt.is_none();
+                    if selected {
+                        selected_line = lines.len();
+                    }
+                    let label = match &item.pointer {
+                        Some(pointer) => display_pointer(pointer).to_string(),
+                        None => "(computed)".to_string(),
+                    };
+                    lines.push(Line::from(Span::styled(
+                        label,
+                        match selected {
+                            true => self.theme.cursor,
+                            false => self.theme.label,
+                        },
+                    )));
+                    let text = serde_json::to_string_pretty(&item.value).unwrap_or_default();
+                    lines.extend(text.lines().map(|line| Line::from(format!("  {}", line))));
+                }
+            }
+            Err(e) => lines.push(Line::styled(e.clone(), self.theme.error)),
+        }
+        let inner = block.inner(area);
+        let scroll = selected_line.saturating_sub(inner.height as usize / 2) as u16;
+        frame.render_widget(Paragraph::new(lines).scroll((scroll, 0)).block(block), area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn values(expression: &str, input: &Value) -> Result<Vec<Value>, String> {
+        run(expression, input, "").map(|items| items.into_iter().map(|item| item.value).collect())
+    }
+
+    #[test]
+    fn test_query() {
+        let document = json!({"users": [
+            {"name": "a", "age": 30, "tags": ["x"]},
+            {"name": "b", "age": 17},
+            {"name": "c", "age": 45, "admin": true}
+        ]});
+        assert_eq!(values(".users[1].name", &document), Ok(vec![json!("b")]));
+        assert_eq!(
+            values(
+                ".users[] | select(.age >= 18 and .admin != true) | .name",
+                &document
+            ),
+            Ok(vec![json!("a")])
+        );
+        assert_eq!(
+            values(".users | map(.age > 20)", &document),
+            Ok(vec![json!([true, false, true])])
+        );
+        assert_eq!(
+            values(
+                "[.users[].tags[]?], (.users | length), .users[-1].admin",
+                &document
+            ),
+            Ok(vec![json!(["x"]), json!(3), json!(true)])
+        );
+        assert_eq!(
+            values("[.. | select(type == \"string\")] | length", &document),
+            Ok(vec![json!(4)])
+        );
+        assert_eq!(
+            values(".users[0] | keys", &document),
+            Ok(vec![json!(["age", "name", "tags"])])
+        );
+
+        let items = run(".users[] | select(.name == \"c\") | .age", &document, "").unwrap();
+        assert_eq!(items[0].pointer.as_deref(), Some("/users/2/age"));
+        let items = run(".[\"a/b\"]", &json!({"a/b": 1}), "/x").unwrap();
+        assert_eq!(items[0].pointer.as_deref(), Some("/x/a~1b"));
+
+        // numbers compare exactly, objects by keys then values
+        let numbers: Value = serde_json::from_str(
+            r#"{"a": 9007199254740993, "b": 9007199254740992, "c": -2.50, "d": -2.5e0,
+                "x": {"a": 1}, "y": {"a": 2}, "z": {"b": 0}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            values(
+                "(.a > .b), (.a <= .b), (.c >= .d), (.c < 0), (.x >= .y), (.y < .z)",
+                &numbers
+            ),
+            Ok(vec![
+                json!(true),
+                json!(false),
+                json!(true),
+                json!(true),
+                json!(false),
+                json!(true)
+            ])
+        );
+        assert_eq!(
+            values(".c | length", &numbers).map(|v| v[0].to_string()),
+            Ok("2.50".to_string())
+        );
+        assert_eq!(values("5 | length", &numbers), Ok(vec![json!(5)]));
+
+        assert!(values(".users.name", &document).is_err());
+        assert!(values(".users[", &document).is_err());
+        assert!(values("sort", &document).is_err());
+    }
+}
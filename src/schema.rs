@@ -0,0 +1,1164 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::{
+    app::{App, CurrentScreen, CurrentlyEditing},
+    edit::{display_pointer, insert_after, split_pointer, unescape_pointer_token},
+    json::{escape_pointer_token, number_to_f64, same_number},
+    palette::fuzzy_score,
+    patch::Operation,
+    text_input::TextInput,
+    ui::centered_rect,
+};
+
+/// what saving a document that does not match its schema does
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum OnInvalid {
+    #[default]
+    Warn,
+    Block,
+}
+
+/// the `[schema]` section of the config
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchemaConfig {
+    pub save: OnInvalid,
+    /// file globs, or `$schema` URLs, mapped to local schema files
+    pub catalogue: HashMap<String, String>,
+}
+
+impl SchemaConfig {
+    /// the local schema for a `$schema` URL, or for the path of the edited file
+    pub fn lookup(&self, name: &str) -> Option<PathBuf> {
+        if let Some(path) = self.catalogue.get(name) {
+            return Some(expand_home(path));
+        }
+        let mut patterns: Vec<(&String, &String)> = self.catalogue.iter().collect();
+        patterns.sort();
+        patterns
+            .into_iter()
+            .find(|(pattern, _)| {
+                glob::Pattern::new(pattern).is_ok_and(|pattern| {
+                    pattern.matches(name)
+                        || Path::new(name)
+                            .file_name()
+                            .is_some_and(|file| pattern.matches(&file.to_string_lossy()))
+                })
+            })
+            .map(|(_, path)| expand_home(path))
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// a place where the document does not match the schema
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaError {
+    pub pointer: String,
+    pub message: String,
+}
+
+/// a loaded schema, `root` resolves the `$ref`s
+pub struct Schema {
+    pub root: Value,
+    pub source: String,
+}
+
+pub fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn is_integer(value: &Value) -> bool {
+    value
+        .as_number()
+        .is_some_and(|number| number_to_f64(number).0.fract() == 0.0)
+}
+
+/// JSON equality, numbers being equal by value (`1` and `1.0`)
+pub fn same(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => same_number(a, b),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).is_some_and(|b| same(a, b)))
+        }
+        _ => a == b,
+    }
+}
+
+fn number(schema: &Map<String, Value>, keyword: &str) -> Option<f64> {
+    schema
+        .get(keyword)
+        .and_then(Value::as_number)
+        .map(|number| number_to_f64(number).0)
+}
+
+fn count(schema: &Map<String, Value>, keyword: &str) -> Option<usize> {
+    schema
+        .get(keyword)
+        .and_then(Value::as_u64)
+        .map(|count| count as usize)
+}
+
+impl Schema {
+    /// read a schema file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let root = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(Self {
+            root,
+            source: path.display().to_string(),
+        })
+    }
+
+    /// the schema a `$ref` points to, only references inside the schema are supported
+    pub fn resolve<'s>(&'s self, reference: &str) -> Option<&'s Value> {
+        let pointer = reference.strip_prefix('#')?;
+        self.root.pointer(pointer)
+    }
+
+    /// every place where `value` does not match the schema
+    pub fn validate(&self, value: &Value) -> Vec<SchemaError> {
+        let mut errors = Vec::new();
+        self.check(&self.root, value, "", &mut errors, 0);
+        errors
+    }
+
+    pub fn is_valid(&self, schema: &Value, value: &Value) -> bool {
+        let mut errors = Vec::new();
+        self.check(schema, value, "", &mut errors, 0);
+        errors.is_empty()
+    }
+
+    /// `schema` with its references resolved and the subschemas of its combinators,
+    /// whatever branch the value takes, to guide the edition
+    fn collect<'s>(
+        &'s self,
+        schema: &'s Value,
+        schemas: &mut Vec<&'s Map<String, Value>>,
+        depth: usize,
+    ) {
+        let Some(schema) = schema.as_object() else {
+            return;
+        };
+        if depth > 32 {
+            return;
+        }
+        schemas.push(schema);
+        if let Some(target) = schema
+            .get("$ref")
+            .and_then(Value::as_str)
+            .and_then(|reference| self.resolve(reference))
+        {
+            self.collect(target, schemas, depth + 1);
+        }
+        for keyword in ["allOf", "anyOf", "oneOf"] {
+            for branch in schema
+                .get(keyword)
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                self.collect(branch, schemas, depth + 1);
+            }
+        }
+        for keyword in ["then", "else"] {
+            if let Some(branch) = schema.get(keyword) {
+                self.collect(branch, schemas, depth + 1);
+            }
+        }
+    }
+
+    /// the schemas of the child `token` of a value matching `schema`
+    fn children<'s>(schema: &'s Map<String, Value>, token: &str, in_array: bool) -> Vec<&'s Value> {
+        if in_array {
+            let idx = token.parse::<usize>().ok();
+            let tuple = schema
+                .get("prefixItems")
+                .or_else(|| schema.get("items").filter(|items| items.is_array()))
+                .and_then(Value::as_array);
+            return match (tuple, idx) {
+                (Some(tuple), Some(idx)) if idx < tuple.len() => vec![&tuple[idx]],
+                (Some(_), _) => schema
+                    .get("additionalItems")
+                    .or_else(|| schema.get("items").filter(|items| !items.is_array()))
+                    .into_iter()
+                    .collect(),
+                (None, _) => schema.get("items").into_iter().collect(),
+            };
+        }
+        if let Some(child) = schema
+            .get("properties")
+            .and_then(|properties| properties.get(token))
+        {
+            return vec![child];
+        }
+        let matching: Vec<&Value> = schema
+            .get("patternProperties")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .filter(|(pattern, _)| Regex::new(pattern).is_ok_and(|regex| regex.is_match(token)))
+            .map(|(_, child)| child)
+            .collect();
+        if matching.is_empty() {
+            schema.get("additionalProperties").into_iter().collect()
+        } else {
+            matching
+        }
+    }
+
+    /// the schemas applying to the node at `pointer` in `document`, which does not have
+    /// to exist yet, only its parent
+    pub fn schemas_at<'s>(
+        &'s self,
+        document: &Value,
+        pointer: &str,
+    ) -> Vec<&'s Map<String, Value>> {
+        let mut schemas = Vec::new();
+        self.collect(&self.root, &mut schemas, 0);
+        let mut value = Some(document);
+        for token in pointer.split('/').skip(1).map(unescape_pointer_token) {
+            let in_array = matches!(value, Some(Value::Array(_)));
+            let mut next = Vec::new();
+            for schema in &schemas {
+                for child in Self::children(schema, &token, in_array) {
+                    self.collect(child, &mut next, 0);
+                }
+            }
+            schemas = next;
+            value = match value {
+                Some(Value::Object(obj)) => obj.get(&token),
+                Some(Value::Array(array)) => {
+                    token.parse::<usize>().ok().and_then(|idx| array.get(idx))
+                }
+                _ => None,
+            };
+        }
+        schemas
+    }
+
+    fn check(
+        &self,
+        schema: &Value,
+        value: &Value,
+        pointer: &str,
+        errors: &mut Vec<SchemaError>,
+        depth: usize,
+    ) {
+        let schema = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => {
+                return errors.push(SchemaError {
+                    pointer: pointer.to_string(),
+                    message: "no value is allowed here".to_string(),
+                })
+            }
+            Value::Object(schema) => schema,
+            _ => return,
+        };
+        // a cycle of references
+        if depth > 64 {
+            return;
+        }
+        let mut messages = Vec::new();
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            if let Some(target) = self.resolve(reference) {
+                self.check(target, value, pointer, errors, depth + 1);
+            }
+        }
+
+        if let Some(types) = schema.get("type") {
+            let types: Vec<&str> = match types {
+                Value::String(name) => vec![name.as_str()],
+                Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            let actual = type_name(value);
+            let matches = types
+                .iter()
+                .any(|name| *name == actual || (*name == "integer" && is_integer(value)));
+            if !types.is_empty() && !matches {
+                messages.push(format!("expected {}, found {}", types.join(" or "), actual));
+            }
+        }
+        if let Some(Value::Array(allowed)) = schema.get("enum") {
+            if !allowed.iter().any(|allowed| same(allowed, value)) {
+                let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+                messages.push(format!("must be one of {}", allowed.join(", ")));
+            }
+        }
+        if let Some(constant) = schema.get("const") {
+            if !same(constant, value) {
+                messages.push(format!("must be {}", constant));
+            }
+        }
+
+        match value {
+            Value::Number(actual) => {
+                let actual = number_to_f64(actual).0;
+                if let Some(minimum) = number(schema, "minimum").filter(|min| actual < *min) {
+                    messages.push(format!("must be at least {}", minimum));
+                }
+                if let Some(maximum) = number(schema, "maximum").filter(|max| actual > *max) {
+                    messages.push(format!("must be at most {}", maximum));
+                }
+                if let Some(minimum) =
+                    number(schema, "exclusiveMinimum").filter(|min| actual <= *min)
+                {
+                    messages.push(format!("must be more than {}", minimum));
+                }
+                if let Some(maximum) =
+                    number(schema, "exclusiveMaximum").filter(|max| actual >= *max)
+                {
+                    messages.push(format!("must be less than {}", maximum));
+                }
+                if let Some(factor) = number(schema, "multipleOf").filter(|factor| *factor > 0.0) {
+                    let quotient = actual / factor;
+                    if (quotient - quotient.round()).abs() > 1e-9 {
+                        messages.push(format!("must be a multiple of {}", factor));
+                    }
+                }
+            }
+            Value::String(text) => {
+                let length = text.chars().count();
+                if let Some(min) = count(schema, "minLength").filter(|min| length < *min) {
+                    messages.push(format!("must be at least {} characters long", min));
+                }
+                if let Some(max) = count(schema, "maxLength").filter(|max| length > *max) {
+                    messages.push(format!("must be at most {} characters long", max));
+                }
+                if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+                    if Regex::new(pattern).is_ok_and(|regex| !regex.is_match(text)) {
+                        messages.push(format!("must match {}", pattern));
+                    }
+                }
+            }
+            Value::Array(items) => {
+                if let Some(min) = count(schema, "minItems").filter(|min| items.len() < *min) {
+                    messages.push(format!("must have at least {} items", min));
+                }
+                if let Some(max) = count(schema, "maxItems").filter(|max| items.len() > *max) {
+                    messages.push(format!("must have at most {} items", max));
+                }
+                if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+                    let duplicated = items
+                        .iter()
+                        .enumerate()
+                        .any(|(i, a)| items[..i].iter().any(|b| same(a, b)));
+                    if duplicated {
+                        messages.push("items must be unique".to_string());
+                    }
+                }
+                if let Some(contains) = schema.get("contains") {
+                    if !items.iter().any(|item| self.is_valid(contains, item)) {
+                        messages.push("no item matches \"contains\"".to_string());
+                    }
+                }
+                // `prefixItems` then `items`, or the draft 7 `items` array then `additionalItems`
+                let (prefix, rest) = match (schema.get("prefixItems"), schema.get("items")) {
+                    (Some(Value::Array(prefix)), rest) => (prefix.as_slice(), rest),
+                    (_, Some(Value::Array(prefix))) => {
+                        (prefix.as_slice(), schema.get("additionalItems"))
+                    }
+                    (_, rest) => (&[][..], rest),
+                };
+                for (idx, item) in items.iter().enumerate() {
+                    let item_schema = prefix.get(idx).or(rest);
+                    if let Some(item_schema) = item_schema {
+                        let item_pointer = format!("{}/{}", pointer, idx);
+                        self.check(item_schema, item, &item_pointer, errors, depth + 1);
+                    }
+                }
+            }
+            Value::Object(obj) => {
+                if let Some(min) = count(schema, "minProperties").filter(|min| obj.len() < *min) {
+                    messages.push(format!("must have at least {} properties", min));
+                }
+                if let Some(max) = count(schema, "maxProperties").filter(|max| obj.len() > *max) {
+                    messages.push(format!("must have at most {} properties", max));
+                }
+                if let Some(Value::Array(required)) = schema.get("required") {
+                    for key in required.iter().filter_map(Value::as_str) {
+                        if !obj.contains_key(key) {
+                            messages.push(format!("missing required property \"{}\"", key));
+                        }
+                    }
+                }
+                if let Some(Value::Object(dependencies)) = schema.get("dependentRequired") {
+                    for (key, required) in dependencies
+                        .iter()
+                        .filter(|(key, _)| obj.contains_key(*key))
+                    {
+                        for other in required
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .filter_map(Value::as_str)
+                        {
+                            if !obj.contains_key(other) {
+                                messages.push(format!(
+                                    "\"{}\" requires the property \"{}\"",
+                                    key, other
+                                ));
+                            }
+                        }
+                    }
+                }
+                let properties = schema.get("properties").and_then(Value::as_object);
+                let patterns: Vec<(Regex, &Value)> = schema
+                    .get("patternProperties")
+                    .and_then(Value::as_object)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|(pattern, schema)| Some((Regex::new(pattern).ok()?, schema)))
+                    .collect();
+                for (key, item) in obj {
+                    let item_pointer = format!("{}/{}", pointer, escape_pointer_token(key));
+                    if let Some(names) = schema.get("propertyNames") {
+                        if !self.is_valid(names, &Value::String(key.clone())) {
+                            errors.push(SchemaError {
+                                pointer: item_pointer.clone(),
+                                message: format!("the name \"{}\" is not allowed", key),
+                            });
+                        }
+                    }
+                    let mut matched = false;
+                    if let Some(property) = properties.and_then(|properties| properties.get(key)) {
+                        matched = true;
+                        self.check(property, item, &item_pointer, errors, depth + 1);
+                    }
+                    for (_, pattern_schema) in
+                        patterns.iter().filter(|(regex, _)| regex.is_match(key))
+                    {
+                        matched = true;
+                        self.check(pattern_schema, item, &item_pointer, errors, depth + 1);
+                    }
+                    if !matched {
+                        match schema.get("additionalProperties") {
+                            Some(Value::Bool(false)) => errors.push(SchemaError {
+                                pointer: item_pointer,
+                                message: format!("the property \"{}\" is not allowed", key),
+                            }),
+                            Some(additional) => {
+                                self.check(additional, item, &item_pointer, errors, depth + 1)
+                            }
+                            None => {}
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(Value::Array(schemas)) = schema.get("allOf") {
+            for schema in schemas {
+                self.check(schema, value, pointer, errors, depth + 1);
+            }
+        }
+        if let Some(Value::Array(schemas)) = schema.get("anyOf") {
+            if !schemas.iter().any(|schema| self.is_valid(schema, value)) {
+                messages.push("matches none of \"anyOf\"".to_string());
+            }
+        }
+        if let Some(Value::Array(schemas)) = schema.get("oneOf") {
+            let matching = schemas
+                .iter()
+                .filter(|schema| self.is_valid(schema, value))
+                .count();
+            if matching != 1 {
+                messages.push(format!("matches {} of \"oneOf\" instead of one", matching));
+            }
+        }
+        if let Some(not) = schema.get("not") {
+            if self.is_valid(not, value) {
+                messages.push("must not match \"not\"".to_string());
+            }
+        }
+        if let Some(condition) = schema.get("if") {
+            let branch = if self.is_valid(condition, value) {
+                schema.get("then")
+            } else {
+                schema.get("else")
+            };
+            if let Some(branch) = branch {
+                self.check(branch, value, pointer, errors, depth + 1);
+            }
+        }
+        errors.extend(messages.into_iter().map(|message| SchemaError {
+            pointer: pointer.to_string(),
+            message,
+        }));
+    }
+}
+
+/// what the schemas of a node say about it, to guide its edition
+#[derive(Default, Debug)]
+pub struct Hint {
+    pub description: Option<String>,
+    pub types: Vec<String>,
+    pub default: Option<Value>,
+    pub choices: Vec<Value>, // the values of `enum` or `const`
+}
+
+impl Hint {
+    pub fn of(schemas: &[&Map<String, Value>]) -> Self {
+        let mut hint = Hint::default();
+        for schema in schemas {
+            if hint.description.is_none() {
+                hint.description = schema
+                    .get("description")
+                    .or_else(|| schema.get("title"))
+                    .and_then(Value::as_str)
+                    .map(str::to_string);
+            }
+            let types = match schema.get("type") {
+                Some(Value::String(name)) => vec![name.clone()],
+                Some(Value::Array(names)) => names
+                    .iter()
+                    .filter_map(|name| name.as_str().map(str::to_string))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            for name in types {
+                if !hint.types.contains(&name) {
+                    hint.types.push(name);
+                }
+            }
+            if hint.default.is_none() {
+                hint.default = schema.get("default").cloned();
+            }
+            if hint.choices.is_empty() {
+                hint.choices = match (schema.get("enum"), schema.get("const")) {
+                    (Some(Value::Array(values)), _) => values.clone(),
+                    (_, Some(value)) => vec![value.clone()],
+                    _ => Vec::new(),
+                };
+            }
+        }
+        hint
+    }
+
+    /// the value a new node starts with: its default, its first allowed value, or an
+    /// empty value of its type
+    pub fn initial_value(&self) -> Value {
+        if let Some(value) = self.default.as_ref().or(self.choices.first()) {
+            return value.clone();
+        }
+        match self.types.first().map(String::as_str) {
+            Some("string") => Value::String(String::new()),
+            Some("number") | Some("integer") => Value::from(0),
+            Some("boolean") => Value::Bool(false),
+            Some("object") => Value::Object(Map::new()),
+            Some("array") => Value::Array(Vec::new()),
+            _ => Value::Null,
+        }
+    }
+}
+
+/// the property names the schemas declare, with their description
+pub fn property_names(schemas: &[&Map<String, Value>]) -> Vec<(String, Option<String>)> {
+    let mut names: Vec<(String, Option<String>)> = Vec::new();
+    for schema in schemas {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (name, property) in properties.into_iter().flatten() {
+            if names.iter().any(|(known, _)| known == name) {
+                continue;
+            }
+            let description = property
+                .get("description")
+                .or_else(|| property.get("title"))
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            names.push((name.clone(), description));
+        }
+    }
+    names
+}
+
+/// the list of schema errors, to jump to the invalid nodes
+#[derive(Default)]
+pub struct ErrorPanel {
+    pub state: ListState,
+}
+
+impl App<'_> {
+    /// load the schema named by the `$schema` key of the document, or the one the
+    /// catalogue gives for the edited file
+    pub fn discover_schema(&mut self) {
+        let named = self
+            .json_container
+            .inner
+            .get("$schema")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let path = match (&named, self.input_path()) {
+            (Some(name), _) => self.schema_config.lookup(name).or_else(|| {
+                let local = name.strip_prefix("file://").unwrap_or(name);
+                if local.contains("://") {
+                    return None;
+                }
+                // relative to the document
+                let dir = self.input_path().and_then(|path| Path::new(path).parent());
+                Some(dir.map_or(PathBuf::from(local), |dir| dir.join(local)))
+            }),
+            (None, Some(input)) => self.schema_config.lookup(input),
+            (None, None) => None,
+        };
+        match (path, named) {
+            (Some(path), _) => self.use_schema(&path),
+            (None, Some(name)) => {
+                self.status = Some(format!(
+                    "Cannot fetch the schema {}, map it to a local file in the schema catalogue",
+                    name
+                ))
+            }
+            (None, None) => {}
+        }
+    }
+
+    pub fn use_schema(&mut self, path: &Path) {
+        match Schema::load(path) {
+            Ok(schema) => {
+                self.schema = Some(schema);
+                self.validated_revision = None;
+                self.revalidate();
+                if !self.schema_errors.is_empty() {
+                    self.status = Some(format!(
+                        "{} schema error(s), see the error panel",
+                        self.schema_errors.len()
+                    ));
+                }
+            }
+            Err(e) => self.status = Some(format!("Schema error: {}", e)),
+        }
+    }
+
+    /// validate the document again if it changed since the last validation
+    pub fn revalidate(&mut self) {
+        let Some(schema) = &self.schema else {
+            return;
+        };
+        if self.validated_revision == Some(self.json_container.revision) {
+            return;
+        }
+        self.schema_errors = schema.validate(&self.json_container.inner);
+        self.validated_revision = Some(self.json_container.revision);
+        self.json_container.invalid = self
+            .schema_errors
+            .iter()
+            .map(|error| error.pointer.clone())
+            .collect();
+        self.json_container.mark_dirty();
+        // marking the lines dirty is not an edit
+        self.validated_revision = Some(self.json_container.revision);
+    }
+
+    /// whether saving has to stop because the document does not match its schema
+    pub fn save_blocked(&mut self) -> bool {
+        self.revalidate();
+        if self.schema_errors.is_empty() {
+            return false;
+        }
+        match self.schema_config.save {
+            OnInvalid::Block => {
+                self.status = Some(format!(
+                    "Not saved: {} schema error(s), see the error panel",
+                    self.schema_errors.len()
+                ));
+                true
+            }
+            OnInvalid::Warn => false,
+        }
+    }
+
+    pub fn open_error_panel(&mut self) {
+        self.revalidate();
+        if self.schema.is_none() {
+            self.status = Some("No schema".to_string());
+            return;
+        }
+        if self.schema_errors.is_empty() {
+            self.status = Some("The document matches its schema".to_string());
+            return;
+        }
+        let mut panel = ErrorPanel::default();
+        panel.state.select(Some(0));
+        self.error_panel = Some(panel);
+    }
+
+    pub fn handle_error_panel_key(&mut self, key: KeyEvent) {
+        let Some(panel) = &mut self.error_panel else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.error_panel = None,
+            KeyCode::Up => panel.state.select_previous(),
+            KeyCode::Down => panel.state.select_next(),
+            KeyCode::Enter => {
+                let selected = panel.state.selected().unwrap_or_default();
+                self.error_panel = None;
+                if let Some(error) = self.schema_errors.get(selected).cloned() {
+                    self.select_pointer(&error.pointer);
+                    self.status = Some(format!(
+                        "{}: {}",
+                        display_pointer(&error.pointer),
+                        error.message
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn render_error_panel(&mut self, frame: &mut Frame) {
+        let Some(panel) = &mut self.error_panel else {
+            return;
+        };
+        let area = centered_rect(70, 60, frame.area());
+        frame.render_widget(Clear, area);
+        let source = self
+            .schema
+            .as_ref()
+            .map_or("", |schema| schema.source.as_str());
+        let block = Block::default()
+            .title(format!(
+                "Schema errors - {} (Enter to jump, Esc to close)",
+                source
+            ))
+            .borders(Borders::ALL)
+            .style(self.theme.popup);
+        let items: Vec<ListItem> = self
+            .schema_errors
+            .iter()
+            .map(|error| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{}  ", display_pointer(&error.pointer)),
+                        self.theme.key,
+                    ),
+                    Span::styled(error.message.clone(), self.theme.error),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(self.theme.cursor);
+        frame.render_stateful_widget(list, area, &mut panel.state);
+    }
+
+    /// the schema hints for the node at `pointer`
+    pub fn hint_at(&self, pointer: &str) -> Option<Hint> {
+        let schema = self.schema.as_ref()?;
+        let schemas = schema.schemas_at(&self.json_container.inner, pointer);
+        (!schemas.is_empty()).then(|| Hint::of(&schemas))
+    }
+
+    /// the pointer the edited node will have, with the key being typed
+    fn edited_pointer(&self) -> String {
+        let pointer = self.selected_pointer();
+        match (
+            split_pointer(&pointer),
+            self.json_container.key_buffer.value(),
+        ) {
+            (Some((parent, _)), Ok(key)) if self.selected_key().is_some() => {
+                format!("{}/{}", parent, escape_pointer_token(&key))
+            }
+            _ => pointer,
+        }
+    }
+
+    pub fn edited_hint(&self) -> Option<Hint> {
+        self.currently_editing.as_ref()?;
+        self.hint_at(&self.edited_pointer())
+    }
+
+    /// the property names the schema allows for the edited key, the ones matching the
+    /// typed text once it changed, without the keys already used by the siblings
+    pub fn key_suggestions(&self) -> Vec<(String, Option<String>)> {
+        let (Some(schema), Some(key)) = (&self.schema, self.selected_key()) else {
+            return Vec::new();
+        };
+        let pointer = self.selected_pointer();
+        let Some((parent, _)) = split_pointer(&pointer) else {
+            return Vec::new();
+        };
+        let siblings = self.json_container.inner.pointer(parent);
+        let typed = self.json_container.key_buffer.value().unwrap_or_default();
+        let schemas = schema.schemas_at(&self.json_container.inner, parent);
+        let mut names: Vec<(i64, (String, Option<String>))> = property_names(&schemas)
+            .into_iter()
+            .filter(|(name, _)| *name == key || siblings.and_then(|obj| obj.get(name)).is_none())
+            .filter_map(|property| {
+                if typed == key {
+                    return Some((0, property));
+                }
+                fuzzy_score(&typed, &property.0).map(|score| (score, property))
+            })
+            .collect();
+        names.sort_by_key(|(score, _)| -score);
+        names.into_iter().map(|(_, property)| property).collect()
+    }
+
+    /// the values the edited value can be picked from, when the schema lists them
+    pub fn value_choices(&self) -> Vec<Value> {
+        match self.currently_editing {
+            Some(CurrentlyEditing::Value(_)) => self
+                .edited_hint()
+                .map(|hint| hint.choices)
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// the highlighted choice, the current value until another one is highlighted
+    fn selected_choice(&self, choices: &[Value]) -> usize {
+        self.value_choice.unwrap_or_else(|| {
+            let current = self.json_container.inner.pointer(&self.selected_pointer());
+            choices
+                .iter()
+                .position(|choice| Some(choice) == current)
+                .unwrap_or_default()
+        })
+    }
+
+    /// use a suggested key; a member just added gets the initial value of its new property
+    fn accept_key_suggestion(&mut self, name: &str) {
+        self.json_container.key_buffer = TextInput::new(name);
+        self.key_suggestion = None;
+        if self.new_member {
+            let initial = self
+                .edited_hint()
+                .map(|hint| hint.initial_value())
+                .unwrap_or_default();
+            let pointer = self.selected_pointer();
+            self.json_container.input_buffer = TextInput::new(&match &initial {
+                Value::String(text) => text.clone(),
+                value => value.to_string(),
+            });
+            self.replace_at(&pointer, initial);
+            self.select_pointer(&pointer);
+        }
+        self.currently_editing = Some(CurrentlyEditing::Value(self.edited_kind()));
+    }
+
+    /// keys choosing a suggested key or a listed value, returns false for the other keys
+    pub fn handle_suggestion_key(&mut self, key: KeyEvent) -> bool {
+        match self.currently_editing {
+            Some(CurrentlyEditing::Key) => {
+                let suggestions = self.key_suggestions();
+                if suggestions.is_empty() {
+                    return false;
+                }
+                match key.code {
+                    KeyCode::Up | KeyCode::Down => {
+                        // a key on several lines moves its caret first
+                        if !self.json_container.key_buffer.handle_key(key) {
+                            self.key_suggestion = match (key.code, self.key_suggestion) {
+                                (KeyCode::Down, None) => Some(0),
+                                (KeyCode::Down, Some(idx)) => {
+                                    Some((idx + 1).min(suggestions.len() - 1))
+                                }
+                                (_, idx) => idx.and_then(|idx| idx.checked_sub(1)),
+                            };
+                        }
+                    }
+                    KeyCode::Enter if key.modifiers.is_empty() => {
+                        let Some((name, _)) =
+                            self.key_suggestion.and_then(|idx| suggestions.get(idx))
+                        else {
+                            return false;
+                        };
+                        self.accept_key_suggestion(&name.clone());
+                    }
+                    _ => {
+                        // the list changes with the typed text
+                        self.key_suggestion = None;
+                        return false;
+                    }
+                }
+                true
+            }
+            Some(CurrentlyEditing::Value(_)) => {
+                let choices = self.value_choices();
+                if choices.is_empty() {
+                    return false;
+                }
+                let selected = self.selected_choice(&choices);
+                match key.code {
+                    KeyCode::Down => {
+                        self.value_choice = Some((selected + 1).min(choices.len() - 1))
+                    }
+                    KeyCode::Up => self.value_choice = Some(selected.saturating_sub(1)),
+                    KeyCode::Enter => {
+                        let choice = choices[selected].clone();
+                        self.value_choice = None;
+                        self.save_edit(Some(choice));
+                    }
+                    // no free text, only the listed values
+                    KeyCode::Char(_) | KeyCode::Backspace | KeyCode::Delete
+                        if !key.modifiers.contains(KeyModifiers::CONTROL) => {}
+                    _ => return false,
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// add a node after the selected one: the first property of the schema missing from
+    /// the object with its default, and edit it
+    pub fn add_member(&mut self) {
+        let pointer = self.selected_pointer();
+        let parent =
+            split_pointer(&pointer).map_or(String::new(), |(parent, _)| parent.to_string());
+        let (key, value) = match (&self.schema, self.json_container.inner.pointer(&parent)) {
+            (Some(schema), Some(Value::Object(obj))) => {
+                let schemas = schema.schemas_at(&self.json_container.inner, &parent);
+                match property_names(&schemas)
+                    .into_iter()
+                    .find(|(name, _)| !obj.contains_key(name))
+                {
+                    Some((name, _)) => {
+                        let child = format!("{}/{}", parent, escape_pointer_token(&name));
+                        let value = self.hint_at(&child).map(|hint| hint.initial_value());
+                        (Some(name), value.unwrap_or_default())
+                    }
+                    None => (None, Value::Null),
+                }
+            }
+            (Some(_), Some(Value::Array(array))) => {
+                let child = format!("{}/{}", parent, array.len());
+                let value = self.hint_at(&child).map(|hint| hint.initial_value());
+                (None, value.unwrap_or_default())
+            }
+            _ => (None, Value::Null),
+        };
+        let inserted =
+            match insert_after(&mut self.json_container.inner, &pointer, key, value.clone()) {
+                Ok(inserted) => inserted,
+                Err(e) => {
+                    self.status = Some(format!("Cannot add: {}", e));
+                    return;
+                }
+            };
+        self.json_container.mark_dirty();
+        self.record(Operation::Add {
+            path: inserted.clone(),
+            value,
+        });
+        self.select_pointer(&inserted);
+        if self.selected_pointer() != inserted {
+            // out of reach of the selection, outside of the elements of the root array
+            self.status = Some(format!("Added {}", inserted));
+            return;
+        }
+        self.current_screen = CurrentScreen::Editing;
+        self.start_editing();
+        self.new_member = true;
+    }
+
+    /// the side panel of the edit popup: the description of the edited node, its type
+    /// and default, and the suggested keys
+    pub fn render_schema_panel(&self, frame: &mut Frame, area: Rect) {
+        let hint = self.edited_hint().unwrap_or_default();
+        let mut lines = Vec::new();
+        if let Some(description) = &hint.description {
+            lines.push(Line::from(description.clone()));
+            lines.push(Line::default());
+        }
+        if !hint.types.is_empty() {
+            lines.push(Line::styled(
+                format!("type: {}", hint.types.join(" | ")),
+                self.theme.hint,
+            ));
+        }
+        if let Some(default) = &hint.default {
+            lines.push(Line::styled(
+                format!("default: {}", default),
+                self.theme.hint,
+            ));
+        }
+        if let Some(CurrentlyEditing::Key) = self.currently_editing {
+            let suggestions = self.key_suggestions();
+            if !suggestions.is_empty() {
+                lines.push(Line::default());
+                lines.push(Line::styled("Properties (Down to pick)", self.theme.hint));
+            }
+            for (idx, (name, description)) in suggestions.into_iter().enumerate() {
+                let style = if self.key_suggestion == Some(idx) {
+                    self.theme.cursor
+                } else {
+                    self.theme.key
+                };
+                let mut spans = vec![Span::styled(name, style)];
+                if let Some(description) = description {
+                    spans.push(Span::styled(format!("  {}", description), self.theme.hint));
+                }
+                lines.push(Line::from(spans));
+            }
+        }
+        let block = Block::default()
+            .title("Schema")
+            .borders(Borders::ALL)
+            .border_style(self.theme.border_inactive);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+
+    /// the value of the edited node as a list of its allowed values
+    pub fn render_value_choices(&self, frame: &mut Frame, area: Rect, block: Block) {
+        let choices = self.value_choices();
+        let mut state = ListState::default().with_selected(Some(self.selected_choice(&choices)));
+        let items: Vec<ListItem> = choices
+            .iter()
+            .map(|choice| ListItem::new(choice.to_string()))
+            .collect();
+        let list = List::new(items)
+            .block(block.title_bottom("Up/Down to pick"))
+            .highlight_style(self.theme.cursor);
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::keymap::Action;
+
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let schema = Schema {
+            root: json!({
+                "type": "object",
+                "required": ["name"],
+                "properties": {
+                    "name": {"type": "string", "minLength": 1},
+                    "tags": {"type": "array", "items": {"$ref": "#/$defs/tag"}},
+                    "port": {"type": "integer", "maximum": 65535}
+                },
+                "additionalProperties": false,
+                "$defs": {"tag": {"enum": ["a", "b"]}}
+            }),
+            source: String::new(),
+        };
+        assert!(schema
+            .validate(&json!({"name": "x", "tags": ["a"], "port": 80.0}))
+            .is_empty());
+        let errors = schema.validate(&json!({"tags": ["a", "c"], "port": 70000, "other": 1}));
+        let pointers: Vec<&str> = errors.iter().map(|error| error.pointer.as_str()).collect();
+        assert_eq!(pointers, ["/tags/1", "/port", "/other", ""]);
+    }
+
+    #[test]
+    fn test_hints() {
+        let schema = Schema {
+            root: json!({
+                "type": "array",
+                "items": {"$ref": "#/$defs/service"},
+                "$defs": {"service": {
+                    "properties": {"name": {"type": "string"}},
+                    "allOf": [{"properties": {"mode": {"enum": ["dev", "prod"], "description": "run mode"}}}]
+                }}
+            }),
+            source: String::new(),
+        };
+        let document = json!([{"name": "web"}]);
+        let schemas = schema.schemas_at(&document, "/0");
+        let names: Vec<String> = property_names(&schemas)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["name", "mode"]);
+        let hint = Hint::of(&schema.schemas_at(&document, "/0/mode"));
+        assert_eq!(hint.description.as_deref(), Some("run mode"));
+        assert_eq!(hint.initial_value(), json!("dev"));
+        assert_eq!(
+            Hint::of(&schema.schemas_at(&document, "/0/name")).initial_value(),
+            json!("")
+        );
+    }
+
+    #[test]
+    fn test_catalogue() {
+        let config = SchemaConfig {
+            save: OnInvalid::Warn,
+            catalogue: HashMap::from([
+                (
+                    "package.json".to_string(),
+                    "/schemas/package.json".to_string(),
+                ),
+                (
+                    "*.config.json".to_string(),
+                    "/schemas/config.json".to_string(),
+                ),
+            ]),
+        };
+        assert_eq!(
+            config.lookup("web/app.config.json"),
+            Some(PathBuf::from("/schemas/config.json"))
+        );
+        assert_eq!(
+            config.lookup("web/package.json"),
+            Some(PathBuf::from("/schemas/package.json"))
+        );
+        assert_eq!(config.lookup("web/other.json"), None);
+    }
+
+    #[test]
+    fn test_quit_blocked() {
+        let mut app = App::default();
+        app.schema = Some(Schema {
+            root: json!({"type": "object"}),
+            source: String::new(),
+        });
+        app.schema_config.save = OnInvalid::Block;
+        app.current_screen = CurrentScreen::Exiting;
+        assert_eq!(app.run_action(Action::QuitAndWrite), None);
+        assert!(matches!(app.current_screen, CurrentScreen::Main));
+        assert!(app.status.as_deref().unwrap().starts_with("Not saved"));
+        app.json_container.inner = json!({});
+        app.json_container.mark_dirty();
+        assert_eq!(app.run_action(Action::QuitAndWrite), Some(true));
+    }
+}
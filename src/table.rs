@@ -0,0 +1,415 @@
+use ratatui::{
+    layout::{Constraint, Rect},
+    text::Line,
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
+    Frame,
+};
+use serde_json::Value;
+
+use crate::{
+    app::{App, CurrentScreen, ValueKind},
+    edit::display_pointer,
+    json::{compare_values, count_inexact_numbers, escape_pointer_token},
+    patch::Operation,
+};
+
+const MIN_WIDTH: u16 = 3;
+const MAX_DEFAULT_WIDTH: u16 = 30;
+const INDEX_WIDTH: u16 = 5;
+
+/// spreadsheet-like state of the array shown by the table view
+pub struct TableView {
+    pub pointer: String,
+    pub columns: Vec<String>,
+    pub widths: Vec<u16>,
+    pub order: Vec<usize>,           // indices of the elements in display order
+    pub sort: Option<(usize, bool)>, // sorted column and whether it is descending
+    pub column: usize,
+    pub scroll: usize, // first visible column
+    pub state: TableState,
+    pub cell_input: Option<String>, // the text of the cell being edited
+}
+
+/// text of a cell: strings are shown raw, other values as compact json
+fn cell_text(value: Option<&Value>) -> String {
+    match value {
+        None => String::new(),
+        Some(Value::String(string)) => string.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+impl TableView {
+    pub fn new(pointer: String, array: &[Value]) -> Result<Self, String> {
+        let mut columns: Vec<String> = Vec::new();
+        for (i, element) in array.iter().enumerate() {
+            let obj = element
+                .as_object()
+                .ok_or_else(|| format!("element {} is not an object", i))?;
+            for key in obj.keys() {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+        }
+        let widths = columns
+            .iter()
+            .map(|column| {
+                array
+                    .iter()
+                    .map(|element| cell_text(element.get(column)).chars().count())
+                    .chain(std::iter::once(column.chars().count() + 2))
+                    .max()
+                    .unwrap_or_default()
+                    .clamp(MIN_WIDTH as usize, MAX_DEFAULT_WIDTH as usize) as u16
+            })
+            .collect();
+        let mut state = TableState::default();
+        if !array.is_empty() {
+            state.select(Some(0));
+        }
+        Ok(Self {
+            pointer,
+            columns,
+            widths,
+            order: (0..array.len()).collect(),
+            sort: None,
+            column: 0,
+            scroll: 0,
+            state,
+            cell_input: None,
+        })
+    }
+
+    pub fn move_row(&mut self, down: bool) {
+        let Some(row) = self.state.selected() else {
+            return;
+        };
+        if down && row + 1 < self.order.len() {
+            self.state.select(Some(row + 1));
+        } else if !down && row > 0 {
+            self.state.select(Some(row - 1));
+        }
+    }
+
+    pub fn move_row_to_end(&mut self, last: bool) {
+        if !self.order.is_empty() {
+            self.state
+                .select(Some(if last { self.order.len() - 1 } else { 0 }));
+        }
+    }
+
+    pub fn move_column(&mut self, right: bool) {
+        if right && self.column + 1 < self.columns.len() {
+            self.column += 1;
+        } else if !right && self.column > 0 {
+            self.column -= 1;
+        }
+    }
+
+    pub fn resize_column(&mut self, grow: bool) {
+        if let Some(width) = self.widths.get_mut(self.column) {
+            *width = if grow {
+                width.saturating_add(1)
+            } else {
+                width.saturating_sub(1).max(MIN_WIDTH)
+            };
+        }
+    }
+
+    /// index of the selected element and name of the selected column
+    fn selected_cell(&self) -> Option<(usize, &String)> {
+        let row = self.state.selected()?;
+        Some((*self.order.get(row)?, self.columns.get(self.column)?))
+    }
+
+    /// make sure the selected column is visible, returns the range of visible columns
+    fn visible_columns(&mut self, width: u16) -> std::ops::Range<usize> {
+        self.scroll = self.scroll.min(self.column);
+        let mut end = self.visible_end(width);
+        while end <= self.column && self.scroll < self.column {
+            self.scroll += 1;
+            end = self.visible_end(width);
+        }
+        self.scroll..end.max(self.scroll + 1).min(self.columns.len())
+    }
+
+    fn visible_end(&self, width: u16) -> usize {
+        let mut used = INDEX_WIDTH;
+        let mut end = self.scroll;
+        for column_width in &self.widths[self.scroll..] {
+            used = used.saturating_add(column_width + 1);
+            if used > width {
+                break;
+            }
+            end += 1;
+        }
+        end
+    }
+}
+
+impl App<'_> {
+    pub fn open_table(&mut self) {
+        let pointer = self.selected_array_pointer();
+        let array = self
+            .json_container
+            .inner
+            .pointer(&pointer)
+            .and_then(Value::as_array);
+        let table = match array {
+            Some(array) => TableView::new(pointer, array),
+            None => Err("the document is not an array".to_string()),
+        };
+        match table {
+            Ok(table) => {
+                self.table = Some(table);
+                self.current_screen = CurrentScreen::Table;
+            }
+            Err(e) => self.status = Some(format!("Cannot show table: {}", e)),
+        }
+    }
+
+    pub fn close_table(&mut self) {
+        self.table = None;
+        self.current_screen = CurrentScreen::Main;
+    }
+
+    /// cycle the sort of the selected column: ascending, descending, unsorted
+    pub fn sort_table(&mut self) {
+        let Some(table) = &mut self.table else {
+            return;
+        };
+        let Some(array) = self
+            .json_container
+            .inner
+            .pointer(&table.pointer)
+            .and_then(Value::as_array)
+        else {
+            return;
+        };
+        table.sort = match table.sort {
+            Some((column, false)) if column == table.column => Some((column, true)),
+            Some((column, true)) if column == table.column => None,
+            _ => Some((table.column, false)),
+        };
+        table.order = (0..array.len()).collect();
+        if table.columns.is_empty() {
+            table.sort = None;
+            return;
+        }
+        if let Some((column, descending)) = table.sort {
+            let key = &table.columns[column];
+            table.order.sort_by(|a, b| {
+                let ordering = compare_values(array[*a].get(key), array[*b].get(key));
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+            let inexact: usize = array
+                .iter()
+                .filter_map(|element| element.get(key))
+                .map(count_inexact_numbers)
+                .sum();
+            if inexact > 0 {
+                self.status = Some(format!(
+                    "Warning: {} number(s) compared as rounded 64-bit floats",
+                    inexact
+                ));
+            }
+        }
+    }
+
+    pub fn edit_table_cell(&mut self) {
+        let Some(table) = &mut self.table else {
+            return;
+        };
+        let Some((element, column)) = table.selected_cell() else {
+            return;
+        };
+        let value = self
+            .json_container
+            .inner
+            .pointer(&table.pointer)
+            .and_then(|array| array.get(element))
+            .and_then(|element| element.get(column));
+        table.cell_input = Some(cell_text(value));
+    }
+
+    /// write the edited cell back into the document, parsed as the type of the cell, a
+    /// missing cell becomes a string; an invalid text stays in the cell with the reason
+    pub fn commit_table_cell(&mut self) {
+        let Some(table) = &mut self.table else {
+            return;
+        };
+        let Some(input) = table.cell_input.take() else {
+            return;
+        };
+        let Some((element, column)) = table.selected_cell() else {
+            return;
+        };
+        let column = column.clone();
+        let target = self
+            .json_container
+            .inner
+            .pointer_mut(&table.pointer)
+            .and_then(|array| array.get_mut(element))
+            .and_then(Value::as_object_mut);
+        let Some(obj) = target else {
+            return;
+        };
+        // a missing cell reads as empty, so leaving it empty does not create the key
+        if cell_text(obj.get(&column)) == input {
+            return;
+        }
+        let kind = obj.get(&column).map_or(ValueKind::String, ValueKind::of);
+        let value = match kind.parse(&input) {
+            Ok(value) => value,
+            Err(e) => {
+                self.status = Some(format!("Invalid {}: {}", kind.name(), e));
+                table.cell_input = Some(input);
+                return;
+            }
+        };
+        let path = format!(
+            "{}/{}/{}",
+            table.pointer,
+            element,
+            escape_pointer_token(&column)
+        );
+        obj.insert(column, value.clone());
+        self.json_container.mark_dirty();
+        self.record(Operation::Add { path, value });
+    }
+
+    pub fn render_table_view(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(table) = &mut self.table else {
+            return;
+        };
+        let array = self
+            .json_container
+            .inner
+            .pointer(&table.pointer)
+            .and_then(Value::as_array);
+        let Some(array) = array else {
+            return;
+        };
+        let visible = table.visible_columns(area.width.saturating_sub(2));
+
+        let header = std::iter::once(Cell::from("#")).chain(visible.clone().map(|column| {
+            let marker = match table.sort {
+                Some((sorted, false)) if sorted == column => " ▲",
+                Some((sorted, true)) if sorted == column => " ▼",
+                _ => "",
+            };
+            Cell::from(format!("{}{}", table.columns[column], marker))
+        }));
+        let header = Row::new(header).style(self.theme.label);
+
+        let selected = table.state.selected();
+        let rows = table.order.iter().enumerate().map(|(row, element)| {
+            let cells = visible.clone().map(|column| {
+                let is_edited = selected == Some(row) && column == table.column;
+                match (&table.cell_input, is_edited) {
+                    (Some(input), true) => Cell::from(input.clone()).style(self.theme.cursor),
+                    _ => Cell::from(cell_text(array[*element].get(&table.columns[column]))),
+                }
+            });
+            Row::new(std::iter::once(Cell::from(element.to_string())).chain(cells))
+        });
+
+        let widths = std::iter::once(Constraint::Length(INDEX_WIDTH - 1)).chain(
+            visible
+                .clone()
+                .map(|column| Constraint::Length(table.widths[column])),
+        );
+
+        let title = format!(
+            "Table {} ({} rows, column {}/{})",
+            display_pointer(&table.pointer),
+            array.len(),
+            table.column + 1,
+            table.columns.len()
+        );
+        let widget = Table::new(rows, widths)
+            .header(header)
+            .block(
+                Block::default()
+                    .title(Line::from(title))
+                    .borders(Borders::ALL)
+                    .border_style(self.theme.border_active),
+            )
+            .row_highlight_style(self.theme.row_highlight)
+            .cell_highlight_style(self.theme.cell_highlight);
+
+        table
+            .state
+            .select_column(Some(table.column - visible.start + 1));
+        frame.render_stateful_widget(widget, area, &mut table.state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn table_app(document: Value) -> App<'static> {
+        let mut app = App::default();
+        app.json_container.inner = document;
+        app.open_table();
+        app
+    }
+
+    #[test]
+    fn test_table() {
+        let array = [json!({"b": 1, "a": 2}), json!({"c": 3, "a": 4})];
+        let table = TableView::new(String::new(), &array).unwrap();
+        assert_eq!(table.columns, ["b", "a", "c"]);
+        assert_eq!(
+            TableView::new(String::new(), &[json!({}), json!(1)]).err(),
+            Some("element 1 is not an object".to_string())
+        );
+
+        let mut app = table_app(json!([{"n": 2}, {"n": "x"}, {}, {"n": 1}]));
+        app.sort_table();
+        assert_eq!(app.table.as_ref().unwrap().order, [2, 3, 0, 1]);
+        app.sort_table();
+        assert_eq!(app.table.as_ref().unwrap().order, [1, 0, 3, 2]);
+        app.sort_table();
+        assert_eq!(app.table.as_ref().unwrap().order, [0, 1, 2, 3]);
+
+        // no column to sort by
+        let mut app = table_app(json!([{}]));
+        app.sort_table();
+        assert_eq!(app.table.as_ref().unwrap().sort, None);
+    }
+
+    #[test]
+    fn test_commit_cell() {
+        let mut app = table_app(json!([{"s": "123", "n": 1, "b": true}, {"s": "x"}]));
+        let edit = |app: &mut App, row: usize, column: usize, text: &str| {
+            let table = app.table.as_mut().unwrap();
+            table.state.select(Some(row));
+            table.column = column;
+            table.cell_input = Some(text.to_string());
+            app.commit_table_cell();
+        };
+        edit(&mut app, 0, 0, "124");
+        edit(&mut app, 0, 1, "2.5");
+        edit(&mut app, 0, 2, "yes");
+        assert_eq!(
+            app.table.as_ref().unwrap().cell_input.as_deref(),
+            Some("yes")
+        );
+        app.table.as_mut().unwrap().cell_input = None;
+        edit(&mut app, 1, 1, "true");
+        edit(&mut app, 1, 2, "");
+        assert_eq!(
+            app.json_container.inner,
+            json!([{"s": "124", "n": 2.5, "b": true}, {"s": "x", "n": "true"}])
+        );
+    }
+}
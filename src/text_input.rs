@@ -0,0 +1,255 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+    layout::Rect,
+    style::Style,
+    widgets::{Block, Paragraph},
+    Frame,
+};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// a multi-line text input with a caret, used to edit keys and string values
+#[derive(Default)]
+pub struct TextInput {
+    text: String,
+    cursor: usize,     // byte offset in `text`, always on a grapheme boundary
+    pub escaped: bool, // whether `text` holds the JSON escapes (`\n`, `\u00e9`) instead of the characters
+}
+
+/// `text` with the JSON escapes of a string, without the quotes
+fn escape(text: &str) -> String {
+    let quoted = serde_json::Value::String(text.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// `text` with its JSON escapes decoded
+fn unescape(text: &str) -> Result<String, String> {
+    serde_json::from_str(&format!("\"{}\"", text)).map_err(|e| format!("invalid escape: {}", e))
+}
+
+impl TextInput {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            cursor: text.len(),
+            escaped: false,
+        }
+    }
+
+    /// the edited text, with its escapes decoded
+    pub fn value(&self) -> Result<String, String> {
+        if self.escaped {
+            unescape(&self.text)
+        } else {
+            Ok(self.text.clone())
+        }
+    }
+
+    /// switch between showing the escapes and the characters they stand for,
+    /// keeping the caret after the same character
+    pub fn toggle_escapes(&mut self) -> Result<(), String> {
+        let (text, before) = if self.escaped {
+            let text = unescape(&self.text)?;
+            let before =
+                unescape(&self.text[..self.cursor]).map_or(text.len(), |before| before.len());
+            (text, before)
+        } else {
+            (escape(&self.text), escape(&self.text[..self.cursor]).len())
+        };
+        self.text = text;
+        self.cursor = before;
+        self.escaped = !self.escaped;
+        Ok(())
+    }
+
+    fn line_start(&self) -> usize {
+        self.text[..self.cursor]
+            .rfind('\n')
+            .map_or(0, |pos| pos + 1)
+    }
+
+    fn line_end(&self) -> usize {
+        self.text[self.cursor..]
+            .find('\n')
+            .map_or(self.text.len(), |pos| self.cursor + pos)
+    }
+
+    fn previous_boundary(&self) -> Option<usize> {
+        self.text[..self.cursor]
+            .grapheme_indices(true)
+            .next_back()
+            .map(|(pos, _)| pos)
+    }
+
+    fn next_boundary(&self) -> Option<usize> {
+        self.text[self.cursor..]
+            .graphemes(true)
+            .next()
+            .map(|grapheme| self.cursor + grapheme.len())
+    }
+
+    /// the start of the word before the caret
+    fn previous_word(&self) -> usize {
+        self.text[..self.cursor]
+            .split_word_bound_indices()
+            .rev()
+            .find(|(_, word)| word.chars().any(char::is_alphanumeric))
+            .map_or(0, |(pos, _)| pos)
+    }
+
+    /// the end of the word after the caret
+    fn next_word(&self) -> usize {
+        self.text[self.cursor..]
+            .split_word_bound_indices()
+            .find(|(_, word)| word.chars().any(char::is_alphanumeric))
+            .map_or(self.text.len(), |(pos, word)| {
+                self.cursor + pos + word.len()
+            })
+    }
+
+    /// move the caret to the line above or below, at the same display column
+    fn move_line(&mut self, down: bool) -> bool {
+        let column = self.text[self.line_start()..self.cursor].width();
+        let start = if down {
+            match self.text[self.cursor..].find('\n') {
+                Some(pos) => self.cursor + pos + 1,
+                None => return false,
+            }
+        } else {
+            match self.line_start().checked_sub(1) {
+                Some(previous_end) => self.text[..previous_end]
+                    .rfind('\n')
+                    .map_or(0, |pos| pos + 1),
+                None => return false,
+            }
+        };
+        let mut width = 0;
+        self.cursor = start;
+        for grapheme in self.text[start..].graphemes(true) {
+            if grapheme == "\n" || width + grapheme.width() > column {
+                break;
+            }
+            width += grapheme.width();
+            self.cursor += grapheme.len();
+        }
+        true
+    }
+
+    pub fn insert(&mut self, value: char) {
+        self.text.insert(self.cursor, value);
+        self.cursor += value.len_utf8();
+    }
+
+    pub fn insert_str(&mut self, text: &str) {
+        self.text.insert_str(self.cursor, text);
+        self.cursor += text.len();
+    }
+
+    /// handle a key typed in the input, returns false for keys it does not use
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let word = key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Left if word => self.cursor = self.previous_word(),
+            KeyCode::Right if word => self.cursor = self.next_word(),
+            KeyCode::Left => self.cursor = self.previous_boundary().unwrap_or(self.cursor),
+            KeyCode::Right => self.cursor = self.next_boundary().unwrap_or(self.cursor),
+            KeyCode::Home => self.cursor = self.line_start(),
+            KeyCode::End => self.cursor = self.line_end(),
+            KeyCode::Up => return self.move_line(false),
+            KeyCode::Down => return self.move_line(true),
+            KeyCode::Enter if key.modifiers.contains(KeyModifiers::ALT) => self.insert('\n'),
+            KeyCode::Backspace if word => {
+                let start = self.previous_word();
+                self.text.replace_range(start..self.cursor, "");
+                self.cursor = start;
+            }
+            KeyCode::Backspace => {
+                if let Some(start) = self.previous_boundary() {
+                    self.text.replace_range(start..self.cursor, "");
+                    self.cursor = start;
+                }
+            }
+            KeyCode::Delete => {
+                if let Some(end) = self.next_boundary() {
+                    self.text.replace_range(self.cursor..end, "");
+                }
+            }
+            KeyCode::Char('b') if key.modifiers == KeyModifiers::ALT => {
+                self.cursor = self.previous_word()
+            }
+            KeyCode::Char('f') if key.modifiers == KeyModifiers::ALT => {
+                self.cursor = self.next_word()
+            }
+            KeyCode::Char('w') if key.modifiers == KeyModifiers::CONTROL => {
+                let start = self.previous_word();
+                self.text.replace_range(start..self.cursor, "");
+                self.cursor = start;
+            }
+            KeyCode::Char(value) if !word => self.insert(value),
+            _ => return false,
+        }
+        true
+    }
+
+    /// the display column and the line of the caret
+    pub fn cursor_position(&self) -> (u16, u16) {
+        let before = &self.text[..self.cursor];
+        let row = before.matches('\n').count();
+        let column = before[self.line_start()..].width();
+        (column as u16, row as u16)
+    }
+
+    /// render the text in `block`, scrolled to keep the caret visible, and put the
+    /// terminal cursor on the caret when `focused`
+    pub fn render(&self, frame: &mut Frame, area: Rect, block: Block, style: Style, focused: bool) {
+        let inner = block.inner(area);
+        let (column, row) = self.cursor_position();
+        let scroll = (
+            row.saturating_sub(inner.height.saturating_sub(1)),
+            column.saturating_sub(inner.width.saturating_sub(1)),
+        );
+        let paragraph = Paragraph::new(self.text.as_str())
+            .style(style)
+            .block(block)
+            .scroll(scroll);
+        frame.render_widget(paragraph, area);
+        if focused {
+            frame.set_cursor_position((inner.x + column - scroll.1, inner.y + row - scroll.0));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(input: &mut TextInput, code: KeyCode, modifiers: KeyModifiers) {
+        input.handle_key(KeyEvent::new(code, modifiers));
+    }
+
+    #[test]
+    fn test_caret_and_escapes() {
+        let mut input = TextInput::new("hello world\n日本");
+        assert_eq!(input.cursor_position(), (4, 1));
+        press(&mut input, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(input.cursor_position(), (4, 0));
+        press(&mut input, KeyCode::Right, KeyModifiers::CONTROL);
+        assert_eq!(input.cursor_position(), (5, 0));
+        press(&mut input, KeyCode::Right, KeyModifiers::CONTROL);
+        press(&mut input, KeyCode::Char('!'), KeyModifiers::NONE);
+        assert_eq!(input.value().unwrap(), "hello world!\n日本");
+
+        input.toggle_escapes().unwrap();
+        assert_eq!(input.text, "hello world!\\n日本");
+        assert_eq!(input.cursor_position(), (12, 0));
+        press(&mut input, KeyCode::End, KeyModifiers::NONE);
+        for c in "\\u00e9".chars() {
+            press(&mut input, KeyCode::Char(c), KeyModifiers::NONE);
+        }
+        assert_eq!(input.value().unwrap(), "hello world!\n日本é");
+        input.toggle_escapes().unwrap();
+        assert_eq!(input.cursor_position(), (5, 1));
+    }
+}
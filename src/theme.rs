@@ -0,0 +1,294 @@
+use std::str::FromStr;
+
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+
+/// every colour used by the interface, so that none is hard-coded in the widgets
+#[derive(Clone)]
+pub struct Theme {
+    pub border_active: Style,
+    pub border_inactive: Style,
+    pub selected: Style, // the selected element in the json view
+    pub cursor: Style,   // the line under the cursor, and the text being edited
+    pub label: Style,    // the key and index labels, and the table header
+    pub row_highlight: Style,
+    pub cell_highlight: Style,
+    pub popup: Style,
+    pub mode: Style,
+    pub mode_exit: Style,
+    pub divider: Style,
+    pub hint: Style,
+    pub error: Style,
+    pub key: Style,
+    pub string: Style,
+    pub number: Style,
+    pub boolean: Style,
+    pub null: Style,
+    pub punctuation: Style,
+    pub bracket: Style, // the brackets of the focused container
+    pub added: Style,   // the nodes added, in a diff
+    pub removed: Style,
+    pub changed: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+fn fg(color: Color) -> Style {
+    Style::default().fg(color)
+}
+
+fn bg(color: Color) -> Style {
+    Style::default().bg(color)
+}
+
+impl Theme {
+    pub const BUILTIN: [&'static str; 3] = ["dark", "light", "high-contrast"];
+
+    pub fn dark() -> Self {
+        Self {
+            border_active: fg(Color::Green),
+            border_inactive: fg(Color::White),
+            selected: bg(Color::Blue),
+            cursor: bg(Color::LightYellow).fg(Color::Black),
+            label: bg(Color::LightBlue).fg(Color::Black),
+            row_highlight: bg(Color::DarkGray),
+            cell_highlight: bg(Color::Blue),
+            popup: bg(Color::DarkGray),
+            mode: fg(Color::Green),
+            mode_exit: fg(Color::LightRed),
+            divider: fg(Color::White),
+            hint: fg(Color::Gray),
+            error: fg(Color::Red),
+            key: fg(Color::Cyan),
+            string: fg(Color::Green),
+            number: fg(Color::Yellow),
+            boolean: fg(Color::Magenta),
+            null: fg(Color::LightRed),
+            punctuation: Style::default(),
+            bracket: bg(Color::LightYellow)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+            added: fg(Color::Green),
+            removed: fg(Color::Red),
+            changed: fg(Color::Yellow),
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            border_active: fg(Color::Green),
+            border_inactive: fg(Color::DarkGray),
+            selected: bg(Color::Rgb(0xd0, 0xe0, 0xff)),
+            cursor: bg(Color::Rgb(0xff, 0xf0, 0xa0)).fg(Color::Black),
+            label: bg(Color::Rgb(0xc8, 0xe6, 0xff)).fg(Color::Black),
+            row_highlight: bg(Color::Rgb(0xe8, 0xe8, 0xe8)),
+            cell_highlight: bg(Color::Rgb(0xa0, 0xc0, 0xff)),
+            popup: bg(Color::Rgb(0xf0, 0xf0, 0xf0)).fg(Color::Black),
+            mode: fg(Color::Rgb(0x00, 0x70, 0x00)),
+            mode_exit: fg(Color::Rgb(0xb0, 0x00, 0x00)),
+            divider: fg(Color::DarkGray),
+            hint: fg(Color::DarkGray),
+            error: fg(Color::Rgb(0xb0, 0x00, 0x00)),
+            key: fg(Color::Rgb(0x00, 0x50, 0xa0)),
+            string: fg(Color::Rgb(0x00, 0x70, 0x00)),
+            number: fg(Color::Rgb(0x90, 0x50, 0x00)),
+            boolean: fg(Color::Rgb(0x80, 0x00, 0x80)),
+            null: fg(Color::Rgb(0xb0, 0x00, 0x00)),
+            punctuation: fg(Color::Black),
+            bracket: bg(Color::Rgb(0xff, 0xd0, 0x60))
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+            added: fg(Color::Rgb(0x00, 0x70, 0x00)),
+            removed: fg(Color::Rgb(0xb0, 0x00, 0x00)),
+            changed: fg(Color::Rgb(0x90, 0x50, 0x00)),
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            border_active: fg(Color::White).add_modifier(Modifier::BOLD),
+            border_inactive: fg(Color::Gray),
+            selected: bg(Color::White).fg(Color::Black),
+            cursor: bg(Color::Yellow).fg(Color::Black),
+            label: fg(Color::White).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            row_highlight: Style::default().add_modifier(Modifier::REVERSED),
+            cell_highlight: bg(Color::Yellow).fg(Color::Black),
+            popup: bg(Color::Black).fg(Color::White),
+            mode: fg(Color::White).add_modifier(Modifier::BOLD),
+            mode_exit: fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            divider: fg(Color::White),
+            hint: fg(Color::White),
+            error: fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            key: fg(Color::LightCyan).add_modifier(Modifier::BOLD),
+            string: fg(Color::LightGreen),
+            number: fg(Color::LightYellow),
+            boolean: fg(Color::LightMagenta),
+            null: fg(Color::LightRed),
+            punctuation: fg(Color::White),
+            bracket: bg(Color::Yellow)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+            added: fg(Color::LightGreen).add_modifier(Modifier::BOLD),
+            removed: fg(Color::LightRed).add_modifier(Modifier::BOLD),
+            changed: fg(Color::LightYellow).add_modifier(Modifier::BOLD),
+        }
+    }
+
+    /// the theme used when `NO_COLOR` is set: only modifiers, no colour
+    pub fn no_color() -> Self {
+        let plain = Style::default();
+        let reversed = plain.add_modifier(Modifier::REVERSED);
+        let bold = plain.add_modifier(Modifier::BOLD);
+        Self {
+            border_active: bold,
+            border_inactive: plain,
+            selected: reversed,
+            cursor: reversed,
+            label: plain.add_modifier(Modifier::UNDERLINED),
+            row_highlight: bold,
+            cell_highlight: reversed,
+            popup: plain,
+            mode: bold,
+            mode_exit: bold,
+            divider: plain,
+            hint: plain,
+            error: bold,
+            key: bold,
+            string: plain,
+            number: plain,
+            boolean: plain,
+            null: plain.add_modifier(Modifier::ITALIC),
+            punctuation: plain,
+            bracket: reversed.add_modifier(Modifier::BOLD),
+            added: bold,
+            removed: plain.add_modifier(Modifier::CROSSED_OUT),
+            changed: plain.add_modifier(Modifier::UNDERLINED),
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            _ => None,
+        }
+    }
+
+    /// `NO_COLOR` set to any non-empty value disables colours, see https://no-color.org
+    pub fn no_color_requested() -> bool {
+        std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+    }
+}
+
+/// a style as written in the config file, e.g. `key = { fg = "blue", bold = true }`
+#[derive(Deserialize, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct StyleSpec {
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underlined: bool,
+    pub reversed: bool,
+}
+
+impl StyleSpec {
+    fn to_style(&self) -> Result<Style, String> {
+        let parse = |color: &str| {
+            Color::from_str(color).map_err(|_| format!("unknown colour \"{}\"", color))
+        };
+        let mut style = Style::default();
+        if let Some(color) = &self.fg {
+            style = style.fg(parse(color)?);
+        }
+        if let Some(color) = &self.bg {
+            style = style.bg(parse(color)?);
+        }
+        for (enabled, modifier) in [
+            (self.bold, Modifier::BOLD),
+            (self.italic, Modifier::ITALIC),
+            (self.underlined, Modifier::UNDERLINED),
+            (self.reversed, Modifier::REVERSED),
+        ] {
+            if enabled {
+                style = style.add_modifier(modifier);
+            }
+        }
+        Ok(style)
+    }
+}
+
+/// a custom theme from the config file, starting from a built-in theme and
+/// overriding some of its styles
+#[derive(Deserialize, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeSpec {
+    pub base: Option<String>,
+    pub border_active: Option<StyleSpec>,
+    pub border_inactive: Option<StyleSpec>,
+    pub selected: Option<StyleSpec>,
+    pub cursor: Option<StyleSpec>,
+    pub label: Option<StyleSpec>,
+    pub row_highlight: Option<StyleSpec>,
+    pub cell_highlight: Option<StyleSpec>,
+    pub popup: Option<StyleSpec>,
+    pub mode: Option<StyleSpec>,
+    pub mode_exit: Option<StyleSpec>,
+    pub divider: Option<StyleSpec>,
+    pub hint: Option<StyleSpec>,
+    pub error: Option<StyleSpec>,
+    pub key: Option<StyleSpec>,
+    pub string: Option<StyleSpec>,
+    pub number: Option<StyleSpec>,
+    pub boolean: Option<StyleSpec>,
+    pub null: Option<StyleSpec>,
+    pub punctuation: Option<StyleSpec>,
+    pub bracket: Option<StyleSpec>,
+    pub added: Option<StyleSpec>,
+    pub removed: Option<StyleSpec>,
+    pub changed: Option<StyleSpec>,
+}
+
+impl ThemeSpec {
+    pub fn to_theme(&self) -> Result<Theme, String> {
+        let base = self.base.as_deref().unwrap_or("dark");
+        let mut theme =
+            Theme::builtin(base).ok_or_else(|| format!("unknown base theme \"{}\"", base))?;
+        let overrides = [
+            (&self.border_active, &mut theme.border_active),
+            (&self.border_inactive, &mut theme.border_inactive),
+            (&self.selected, &mut theme.selected),
+            (&self.cursor, &mut theme.cursor),
+            (&self.label, &mut theme.label),
+            (&self.row_highlight, &mut theme.row_highlight),
+            (&self.cell_highlight, &mut theme.cell_highlight),
+            (&self.popup, &mut theme.popup),
+            (&self.mode, &mut theme.mode),
+            (&self.mode_exit, &mut theme.mode_exit),
+            (&self.divider, &mut theme.divider),
+            (&self.hint, &mut theme.hint),
+            (&self.error, &mut theme.error),
+            (&self.key, &mut theme.key),
+            (&self.string, &mut theme.string),
+            (&self.number, &mut theme.number),
+            (&self.boolean, &mut theme.boolean),
+            (&self.null, &mut theme.null),
+            (&self.punctuation, &mut theme.punctuation),
+            (&self.bracket, &mut theme.bracket),
+            (&self.added, &mut theme.added),
+            (&self.removed, &mut theme.removed),
+            (&self.changed, &mut theme.changed),
+        ];
+        for (spec, style) in overrides {
+            if let Some(spec) = spec {
+                *style = spec.to_style()?;
+            }
+        }
+        Ok(theme)
+    }
+}
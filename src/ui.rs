@@ -1,12 +1,17 @@
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    style::Style,
     text::{Line, Span, Text},
     widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap},
     Frame,
 };
 
-use crate::app::{App, CurrentScreen, CurrentlyEditing, InputFile};
+use crate::{
+    app::{App, CurrentScreen, CurrentlyEditing, InputFile},
+    edit::display_pointer,
+    keymap::{Action, Context},
+    theme::Theme,
+};
 
 impl<'a> App<'a> {
     pub fn render_title(&self) -> impl Widget {
@@ -19,12 +24,26 @@ impl<'a> App<'a> {
             InputFile::Edition(file) => format!("Editing {}", file),
             InputFile::None => "Creating json".to_string(),
         };
+        let text = self.render_compare_title().unwrap_or(text);
+        let text = match &self.output {
+            Some(output) => format!("{}, writing to {}", text, output),
+            None => text,
+        };
+        let text = match self.readonly {
+            true => format!("{} (read-only)", text),
+            false => text,
+        };
 
         let text = match &self.index_edition {
             Some(val) => format!("{} (index {})", text, val),
             None => text,
         };
 
+        let text = match self.schema_errors.len() {
+            0 => text,
+            errors => format!("{} - {} schema error(s)", text, errors),
+        };
+
         let widget = Paragraph::new(Text::styled(text, Style::default()))
             .centered()
             .block(widget_style);
@@ -37,9 +56,10 @@ impl<'a> App<'a> {
         input_cursor: Option<usize>,
         prefix: String,
         idx: &mut usize,
+        theme: &Theme,
     ) -> Vec<Line<'a>> {
         let style = if input_cursor == Some(*idx) {
-            Style::default().bg(Color::LightYellow)
+            theme.cursor
         } else {
             Style::default()
         };
@@ -48,8 +68,7 @@ impl<'a> App<'a> {
                 let mut text = Vec::new();
                 for (i, value) in array.iter_mut().enumerate() {
                     let new_prefix = format!("{}.{}", prefix, i + 1);
-                    let array_idx =
-                        Span::styled(new_prefix.clone(), Style::default().bg(Color::LightBlue));
+                    let array_idx = Span::styled(new_prefix.clone(), theme.label);
                     text.push(Line::from(array_idx));
                     *idx += 1;
                     text.extend_from_slice(&Self::render_json_struct(
@@ -57,6 +76,7 @@ impl<'a> App<'a> {
                         input_cursor,
                         new_prefix,
                         idx,
+                        theme,
                     ));
                 }
                 text
@@ -66,8 +86,7 @@ impl<'a> App<'a> {
                 for (key, value) in obj.iter_mut() {
                     let key = format!("{}.{}", prefix, key);
                     if !value.is_array() {
-                        let key_span =
-                            Span::styled(key.clone(), Style::default().bg(Color::LightBlue));
+                        let key_span = Span::styled(key.clone(), theme.label);
                         text.push(Line::from(key_span));
                         *idx += 1;
                     }
@@ -76,6 +95,7 @@ impl<'a> App<'a> {
                         input_cursor,
                         key,
                         idx,
+                        theme,
                     ));
                 }
                 text
@@ -100,14 +120,14 @@ impl<'a> App<'a> {
     }
 
     pub fn render_edition(&mut self) -> impl Widget + use<'a> {
-        let border_color = match self.current_screen {
-            CurrentScreen::Editing => Color::Green,
-            _ => Color::White,
+        let border_style = match self.current_screen {
+            CurrentScreen::Editing => self.theme.border_active,
+            _ => self.theme.border_inactive,
         };
 
         let widget_style = Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(border_color))
+            .border_style(border_style)
             .style(Style::default());
 
         let widget = match self.index_edition {
@@ -125,6 +145,7 @@ impl<'a> App<'a> {
                             input_cursor,
                             idx_json.to_string(),
                             &mut idx,
+                            &self.theme,
                         );
                         self.json_container.max_cursor = Some(idx);
                         res
@@ -144,14 +165,14 @@ impl<'a> App<'a> {
     }
 
     fn render_json_view(&self) -> impl Widget + 'a {
-        let border_color = match self.current_screen {
-            CurrentScreen::Main => Color::Green,
-            _ => Color::White,
+        let border_style = match self.current_screen {
+            CurrentScreen::Main => self.theme.border_active,
+            _ => self.theme.border_inactive,
         };
 
         let widget_style = Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(border_color))
+            .border_style(border_style)
             .style(Style::default());
         let lines = self.json_container.lines.clone();
         let json_para = Paragraph::new(lines).block(widget_style);
@@ -162,37 +183,36 @@ impl<'a> App<'a> {
         let current_navigation_text = vec![
             // The first half of the text
             match self.current_screen {
-                CurrentScreen::Main => Span::styled("View Mode", Style::default().fg(Color::Green)),
-                CurrentScreen::Editing => {
-                    Span::styled("Editing Mode", Style::default().fg(Color::Green))
-                }
-                CurrentScreen::Exiting => {
-                    Span::styled("Exiting", Style::default().fg(Color::LightRed))
-                }
+                CurrentScreen::Main => Span::styled("View Mode", self.theme.mode),
+                CurrentScreen::Editing => Span::styled("Editing Mode", self.theme.mode),
+                CurrentScreen::Exiting => Span::styled("Exiting", self.theme.mode_exit),
+                CurrentScreen::Prompt => Span::styled("Command", self.theme.mode),
+                CurrentScreen::Table => Span::styled("Table Mode", self.theme.mode),
             }
             .to_owned(),
             // A white divider bar to separate the two sections
-            Span::styled(" | ", Style::default().fg(Color::White)),
+            Span::styled(" | ", self.theme.divider),
             // The final section of the text, with hints on what the user is editing
             {
-                if self.currently_editing.is_some() {
-                    Span::styled(
-                        "Left Arrow to view mode",
-                        Style::default().fg(Color::DarkGray),
-                    )
+                let hint = if let CurrentScreen::Editing = self.current_screen {
+                    self.keymap
+                        .key_for(Context::Editing, Action::ExitEdit)
+                        .map(|key| format!("{} to view mode", key))
                 } else {
-                    Span::styled(
-                        "Right Arrow to edit mode",
-                        Style::default().fg(Color::DarkGray),
-                    )
-                }
+                    self.keymap
+                        .key_for(Context::Main, Action::EnterEdit)
+                        .map(|key| format!("{} to edit mode", key))
+                };
+                Span::styled(hint.unwrap_or_default(), self.theme.hint)
             },
         ];
 
         let current_navigation_text = match self.current_screen {
             CurrentScreen::Main => current_navigation_text,
             CurrentScreen::Editing => current_navigation_text.into_iter().rev().collect(),
-            CurrentScreen::Exiting => current_navigation_text,
+            CurrentScreen::Exiting | CurrentScreen::Prompt | CurrentScreen::Table => {
+                current_navigation_text
+            }
         };
 
         let mode_footer = Paragraph::new(Line::from(current_navigation_text))
@@ -201,17 +221,22 @@ impl<'a> App<'a> {
         let mode_footer = match self.current_screen {
             CurrentScreen::Main => mode_footer.left_aligned(),
             CurrentScreen::Editing => mode_footer.right_aligned(),
-            CurrentScreen::Exiting => mode_footer.centered(),
+            CurrentScreen::Exiting | CurrentScreen::Prompt | CurrentScreen::Table => {
+                mode_footer.centered()
+            }
         };
 
         mode_footer
     }
 
     fn render_key_hint(&self) -> impl Widget {
-        let key_hint = match self.currently_editing {
-            Some(CurrentlyEditing::Key) => "Editing Key",
-            Some(CurrentlyEditing::Value) => "Editing Value",
-            None => "Viewing",
+        let key_hint = match (&self.status, &self.currently_editing) {
+            (Some(status), _) => status.clone(),
+            (None, Some(CurrentlyEditing::Key)) => "Editing Key".to_string(),
+            (None, Some(CurrentlyEditing::Value(kind))) => {
+                format!("Editing Value ({})", kind.name())
+            }
+            (None, None) => "Viewing".to_string(),
         };
 
         let key_hint = Paragraph::new(Text::styled(key_hint, Style::default()))
@@ -239,61 +264,142 @@ impl<'a> App<'a> {
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
             .split(chunks[1]);
 
-        frame.render_widget(self.render_json_view(), screens[0]);
-        frame.render_widget(self.render_edition(), screens[1]);
+        if self.table.is_some() {
+            self.render_table_view(frame, chunks[1]);
+        } else if self.compare.is_some() {
+            self.render_compare(frame, [screens[0], screens[1]]);
+        } else if self.query.is_some() {
+            frame.render_widget(self.render_json_view(), screens[0]);
+            self.render_query(frame, screens[1]);
+        } else {
+            frame.render_widget(self.render_json_view(), screens[0]);
+            frame.render_widget(self.render_edition(), screens[1]);
+        }
 
         let footer_chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
             .split(chunks[2]);
 
-        frame.render_widget(self.render_footer_mode(), footer_chunks[0]);
+        if self.compare.is_some() {
+            frame.render_widget(self.render_compare_keys(), footer_chunks[0]);
+        } else {
+            frame.render_widget(self.render_footer_mode(), footer_chunks[0]);
+        }
         frame.render_widget(self.render_key_hint(), footer_chunks[1]);
 
-        // if let Some(editing) = &self.currently_editing {
-        //     let popup_block = Block::default()
-        //         .title("Enter a new key-value pair")
-        //         .borders(Borders::NONE)
-        //         .style(Style::default().bg(Color::DarkGray));
-
-        //     let area = centered_rect(60, 25, frame.area());
-        //     frame.render_widget(popup_block, area);
-
-        //     let popup_chunks = Layout::default()
-        //         .direction(Direction::Horizontal)
-        //         .margin(1)
-        //         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-        //         .split(area);
-
-        //     let mut key_block = Block::default().title("Key").borders(Borders::ALL);
-        //     let mut value_block = Block::default().title("Value").borders(Borders::ALL);
-
-        //     let active_style = Style::default().bg(Color::LightYellow).fg(Color::Black);
+        if let Some(editing) = &self.currently_editing {
+            let editing_key = matches!(editing, CurrentlyEditing::Key);
+            let pointer = self.selected_pointer();
+            let popup_block = Block::default()
+                .title(format!("Edit {}", display_pointer(&pointer)))
+                .title_bottom("Enter to save, Tab to switch, Esc to cancel")
+                .borders(Borders::ALL)
+                .style(self.theme.popup);
+
+            // with a schema, the popup is wider to describe the node on the side
+            let guided = self.edited_hint().is_some();
+            let area = if guided {
+                centered_rect(90, 50, frame.area())
+            } else {
+                centered_rect(60, 40, frame.area())
+            };
+            frame.render_widget(Clear, area);
+            frame.render_widget(popup_block, area);
+
+            let constraints = if guided {
+                vec![
+                    Constraint::Percentage(30),
+                    Constraint::Percentage(30),
+                    Constraint::Percentage(40),
+                ]
+            } else {
+                vec![Constraint::Percentage(50), Constraint::Percentage(50)]
+            };
+            let popup_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .margin(1)
+                .constraints(constraints)
+                .split(area);
+            if guided {
+                self.render_schema_panel(frame, popup_chunks[2]);
+            }
 
-        //     match editing {
-        //         CurrentlyEditing::Key => key_block = key_block.style(active_style),
-        //         CurrentlyEditing::Value => value_block = value_block.style(active_style),
-        //     };
+            let field_block = |title: String, active: bool| {
+                let (title, border_style) = if active {
+                    (format!("{} (F2 escapes)", title), self.theme.border_active)
+                } else {
+                    (title, self.theme.border_inactive)
+                };
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(border_style)
+            };
+
+            match self.selected_key() {
+                Some(_) => self.json_container.key_buffer.render(
+                    frame,
+                    popup_chunks[0],
+                    field_block("Key".to_string(), editing_key),
+                    Style::default(),
+                    editing_key,
+                ),
+                None => frame.render_widget(
+                    Paragraph::new("(array element, no key)")
+                        .style(self.theme.hint)
+                        .block(field_block("Key".to_string(), false)),
+                    popup_chunks[0],
+                ),
+            }
 
-        //     let key_text =
-        //         Paragraph::new(self.json_container.input_buffer.clone()).block(key_block);
-        //     frame.render_widget(key_text, popup_chunks[0]);
+            // invalid values are shown in red, with the reason
+            let mut value_block = field_block(
+                format!("Value ({})", self.edited_kind().name()),
+                !editing_key,
+            );
+            let value_style = match self.edited_value() {
+                Some(Err(e)) => {
+                    value_block = value_block.title_bottom(Line::styled(e, self.theme.error));
+                    self.theme.error
+                }
+                _ => Style::default(),
+            };
+            if self.value_choices().is_empty() {
+                self.json_container.input_buffer.render(
+                    frame,
+                    popup_chunks[1],
+                    value_block,
+                    value_style,
+                    !editing_key,
+                );
+            } else {
+                self.render_value_choices(frame, popup_chunks[1], value_block);
+            }
+        }
 
-        //     let value_text =
-        //         Paragraph::new(self.json_container.input_buffer.clone()).block(value_block);
-        //     frame.render_widget(value_text, popup_chunks[1]);
-        // }
+        if let Some(prompt) = &self.prompt {
+            let popup_block = Block::default()
+                .title(prompt.title())
+                .borders(Borders::ALL)
+                .style(self.theme.popup);
+
+            let area = centered_rect(60, 20, frame.area());
+            let input = Paragraph::new(prompt.input.clone()).block(popup_block);
+            frame.render_widget(Clear, area);
+            frame.render_widget(input, area);
+        }
 
         if let CurrentScreen::Exiting = self.current_screen {
             frame.render_widget(Clear, frame.area()); //this clears the entire screen and anything already drawn
             let popup_block = Block::default()
                 .title("Y/N")
                 .borders(Borders::NONE)
-                .style(Style::default().bg(Color::DarkGray));
+                .style(self.theme.popup);
 
             let exit_text = Text::styled(
                 "Would you like to output the buffer as json? (y/n)",
-                Style::default().fg(Color::Red),
+                self.theme.error,
             );
             // the `trim: false` will stop the text from being cut off when over the edge of the block
             let exit_paragraph = Paragraph::new(exit_text)
@@ -303,10 +409,17 @@ impl<'a> App<'a> {
             let area = centered_rect(60, 25, frame.area());
             frame.render_widget(exit_paragraph, area);
         }
+
+        self.render_help(frame);
+        self.render_convert(frame);
+        self.render_error_panel(frame);
+        self.render_diff(frame);
+        self.render_patch_preview(frame);
+        self.render_palette(frame);
     }
 }
 /// helper function to create a centered rect using up certain percentage of the available rect `r`
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     // Cut the given rectangle into three vertical pieces
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)